@@ -6,11 +6,11 @@ use std::time::Duration;
 
 fn main() {
   // Create a GPIO object at pin #69 that'll represent the LED, export it, and
-  // set it as an output
+  // set it as an output that starts off low
   // Adjust the pin number to whatever pin your LED is connected to
-  let mut led = GPIO::new(GPIO_P8_9);
-  led.set_export(DeviceState::Exported).unwrap();
-  led.set_direction(PinDirection::Out).unwrap();
+  let board = Board::new();
+  let led = GPIO::new(&board, P8_9).unwrap();
+  let mut led = led.into_output(PinState::Low).unwrap();
 
   for _ in 1..11 {
     // Toggle the LED on and off every 250ms 10 times
@@ -21,5 +21,5 @@ fn main() {
   }
 
   // Unexport the LED once we're done with it.
-  led.set_export(DeviceState::Unexported).unwrap();
+  led.into_unexported().unwrap();
 }
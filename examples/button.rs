@@ -6,19 +6,27 @@ fn main() {
   // Create a GPIO object at pin #66 that'll represent the button, export it,
   // and set it as an input
   // Adjust the pin number to whatever pin your LED is connected to
-  let button = GPIO::new(GPIO_P8_7);
-  button.set_export(DeviceState::Exported).unwrap();
-  button.set_direction(PinDirection::In).unwrap();
+  let board = Board::new();
+  let button = GPIO::new(&board, P8_7).unwrap();
+  let mut button = button.into_input().unwrap();
+
+  // Generate an interrupt on both presses and releases, so we can sleep while
+  // waiting instead of constantly reading the pin
+  button.set_edge(Edge::Both).unwrap();
   println!("Waiting for button press...");
 
-  for _ in 1..6 {
+  let mut presses = 0;
+  while presses < 5 {
     // Wait for button to be hit and then released 5 times
-    while button.read().unwrap() != PinState::High {}
-    println!("Button hit!");
-    while button.read().unwrap() != PinState::Low {}
-    println!("Button released!");
+    match button.wait_for_edge(None).unwrap() {
+      PinState::High => println!("Button hit!"),
+      PinState::Low => {
+        println!("Button released!");
+        presses += 1;
+      }
+    }
   }
 
   // Unexport the button once we're done with it.
-  button.set_export(DeviceState::Unexported).unwrap();
+  button.into_unexported().unwrap();
 }
@@ -5,12 +5,13 @@ use std::thread;
 use std::time::Duration;
 
 fn main() {
-  // Create a new ADC object using AIN-0 and a scaling factor of 0.
-  let sensor = ADC::new(AIN_0, 0.0);
+  // Create a new ADC object using AIN-0 and a scaling factor of 1.
+  let board = Board::new();
+  let sensor = ADC::new(&board, AIN_0, 1.0).unwrap();
 
-  // Read from the ADC object every 50ms 100 times, and print out the value.
+  // Read from the ADC object every 50ms 100 times, and print out the voltage.
   for _ in 1..101 {
-    println!("{}", sensor.read().unwrap());
+    println!("{:.3} V", sensor.read_voltage().unwrap());
     thread::sleep(Duration::from_millis(50));
   }
 }
@@ -12,27 +12,210 @@
 //! *NOTE:* the ADC inputs on the BeagleBone are limited to 1.8V.
 //! Be careful not to exceed this limit or you may damage the BeagleBone (don't
 //! ask me how I know that!).
+//!
+//! Raw readings are 12-bit counts, from 0 to 4095 for 0 V to 1.8 V.
+//! `read_voltage()` converts them to volts, correcting for the offset and
+//! gain errors of each board's ADC with a `Calibration`.
+//!
+//! Under heavy load the TI ADC driver sometimes gives up on a conversion and
+//! fails the read with `EAGAIN`; such reads are retried a few times.
+//! To sample several channels at once, see `adc_buffer`; to smooth noisy
+//! readings, see `adc_filter`.
 
+use board::Board;
 use errors::*;
 use pins::Pin;
-use util::*;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+/// The reference voltage of the ADC, i.e. the voltage of a full scale reading.
+const REFERENCE_VOLTAGE: f32 = 1.8;
+/// The full scale reading of the 12-bit ADC.
+const FULL_SCALE: u32 = 4095;
+/// The number of times a read is attempted while the driver answers `EAGAIN`.
+const READ_ATTEMPTS: u32 = 10;
 
 /// Represents a pin configured as an ADC.
 #[derive(Debug)]
 pub struct ADC {
-  adc_num: u16,
+  adc_num: u8,
+  adc_path: PathBuf,
   scaling_factor: f32,
+  calibration: Calibration,
+}
+
+/// An affine correction of ADC voltages: `gain * voltage + offset`.
+///
+/// Calibrations can be stored as strings, e.g. in a per-board config file,
+/// with `to_string()` and read back with `parse()`.
+///
+/// # Examples
+///
+/// ```
+/// use libbeaglebone::adc::Calibration;
+///
+/// // The ADC read 0.105 V and 1.495 V for reference voltages of 0.1 V and
+/// // 1.5 V.
+/// let cal = Calibration::from_points((0.105, 0.1), (1.495, 1.5)).unwrap();
+/// assert!((cal.apply(1.2) - 1.2029).abs() < 0.0001);
+///
+/// // Store the calibration and read it back
+/// let stored = cal.to_string();
+/// assert_eq!(stored.parse::<Calibration>().unwrap(), cal);
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Calibration {
+  /// The voltage added after the gain is applied.
+  pub offset: f32,
+  /// The factor the voltage read is multiplied by.
+  pub gain: f32,
+}
+
+impl Calibration {
+  /// Creates a new calibration from an offset and a gain.
+  pub fn new(offset: f32, gain: f32) -> Calibration {
+    Calibration {
+      offset: offset,
+      gain: gain,
+    }
+  }
+
+  /// Computes a calibration from two `(read, actual)` voltage pairs, e.g.
+  /// from reading two known reference voltages.
+  ///
+  /// The references should be far apart, ideally near either end of the
+  /// ADC's range.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if both voltages read are the same.
+  pub fn from_points(first: (f32, f32), second: (f32, f32)) -> Result<Calibration> {
+    let (read1, actual1) = first;
+    let (read2, actual2) = second;
+    if read1 == read2 {
+      bail!(ErrorKind::InvalidValue("ADC calibration".to_string(), read1.to_string()));
+    }
+    let gain = (actual2 - actual1) / (read2 - read1);
+    Ok(Calibration::new(actual1 - gain * read1, gain))
+  }
+
+  /// Corrects a voltage read by the ADC.
+  pub fn apply(&self, voltage: f32) -> f32 {
+    self.gain * voltage + self.offset
+  }
+}
+
+impl Default for Calibration {
+  /// Returns the calibration that leaves voltages unchanged.
+  fn default() -> Calibration {
+    Calibration::new(0.0, 1.0)
+  }
+}
+
+impl fmt::Display for Calibration {
+  /// Formats the calibration as `offset=<volts> gain=<factor>`, without
+  /// losing precision.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "offset={} gain={}", self.offset, self.gain)
+  }
+}
+
+impl FromStr for Calibration {
+  type Err = Error;
+
+  /// Parses a calibration formatted by `to_string()`.
+  fn from_str(s: &str) -> Result<Calibration> {
+    let mut offset = None;
+    let mut gain = None;
+    for field in s.split_whitespace() {
+      let mut parts = field.splitn(2, '=');
+      match (parts.next(), parts.next().map(|value| value.parse::<f32>())) {
+        (Some("offset"), Some(Ok(value))) => offset = Some(value),
+        (Some("gain"), Some(Ok(value))) => gain = Some(value),
+        _ => bail!(ErrorKind::InvalidValue("ADC calibration".to_string(), s.to_string())),
+      }
+    }
+    match (offset, gain) {
+      (Some(offset), Some(gain)) => Ok(Calibration::new(offset, gain)),
+      _ => bail!(ErrorKind::InvalidValue("ADC calibration".to_string(), s.to_string())),
+    }
+  }
 }
 
 impl ADC {
   /// Creates a new ADC object.
-  pub fn new(pin: Pin, scaling_factor: f32) -> ADC {
-    ADC {
-      adc_num: (pin as u16) - 1000,
-      scaling_factor: scaling_factor,
+  ///
+  /// # Errors
+  ///
+  /// Fails if the pin isn't one of the analog inputs.
+  pub fn new(board: &Board, pin: Pin, scaling_factor: f32) -> Result<ADC> {
+    match pin.adc_channel() {
+      Some(adc_num) => ADC::from_channel(board, adc_num, scaling_factor),
+      None => bail!(ErrorKind::InvalidPin(pin.to_string())),
     }
   }
 
+  /// Creates a new ADC object for an ADC channel, from 0 to 7.
+  ///
+  /// Channel 7 isn't routed to a header pin, so this is the only way to read
+  /// it; it used to be reachable through the `AIN_7` pin alias.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let ain7 = ADC::from_channel(&Board::new(), 7, 1.0).unwrap();
+  /// println!("{}", ain7.read().unwrap());
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if `adc_num` isn't between 0 and 7.
+  pub fn from_channel(board: &Board, adc_num: u8, scaling_factor: f32) -> Result<ADC> {
+    if adc_num > 7 {
+      bail!(ErrorKind::InvalidPin(format!("AIN{}", adc_num)));
+    }
+    Ok(ADC {
+      adc_num: adc_num,
+      adc_path: board.sysfs_path(format!(
+        "bus/iio/devices/iio:device0/in_voltage{}_raw",
+        adc_num
+      )),
+      scaling_factor: scaling_factor,
+      calibration: Calibration::default(),
+    })
+  }
+
+  /// Returns the calibration applied by `read_voltage()`.
+  pub fn calibration(&self) -> Calibration {
+    self.calibration
+  }
+
+  /// Sets the calibration applied by `read_voltage()`.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::adc::Calibration;
+  /// use libbeaglebone::prelude::*;
+  /// use std::fs;
+  ///
+  /// let mut sensor = ADC::new(&Board::new(), AIN_0, 1.0).unwrap();
+  ///
+  /// // Load the calibration stored for this board
+  /// let stored = fs::read_to_string("/etc/adc0-calibration").unwrap();
+  /// sensor.set_calibration(stored.trim().parse().unwrap());
+  /// ```
+  pub fn set_calibration(&mut self, calibration: Calibration) {
+    self.calibration = calibration;
+  }
+
   /// Reads the raw voltage of the ADC.
   ///
   /// # Examples
@@ -41,25 +224,13 @@ impl ADC {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new ADC pin using ADC #0 and no scaling factor.
-  /// let mut sensor = ADC::new(AIN_0, 0.0);
+  /// let mut sensor = ADC::new(&Board::new(), AIN_0, 1.0).unwrap();
   ///
   /// // Read the ADC value.
   /// sensor.read().unwrap();
   /// ```
   pub fn read(&self) -> Result<u32> {
-    let path = format!(
-      "/sys/bus/iio/devices/iio:device0/in_voltage{}_raw",
-      &self.adc_num
-    );
-
-    Ok(
-      path.read_file()
-          .chain_err(|| format!("Failed to read from ADC #{}", &self.adc_num))?
-          .trim()
-          .to_string()
-          .parse::<u32>()
-          .chain_err(|| format!("Failed to parse ADC #{} value", &self.adc_num))?,
-    )
+    read_raw(&self.adc_path, &format!("ADC #{}", self.adc_num))
   }
 
   /// Reads the raw voltage of the ADC and applies a scaling factor to it.
@@ -75,25 +246,57 @@ impl ADC {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new ADC pin using ADC #6 and a scaling factor of 0.0122.
-  /// let mut sensor = ADC::new(AIN_6, 0.0122);
+  /// let mut sensor = ADC::new(&Board::new(), AIN_6, 0.0122).unwrap();
   ///
   /// // Read the ADC value and scale it to degrees Celsius.
   /// sensor.scaled_read().unwrap();
   /// ```
   pub fn scaled_read(&self) -> Result<f32> {
-    let path = format!(
-      "/sys/bus/iio/devices/iio:device0/in_voltage{}_raw",
-      &self.adc_num
-    );
-
-    let raw_value =
-      path.read_file()
-          .chain_err(|| format!("Failed to read from ADC #{}", &self.adc_num))?
-          .trim()
-          .to_string()
-          .parse::<u32>()
-          .chain_err(|| format!("Failed to parse ADC #{} value", &self.adc_num))?;
-
-    Ok(raw_value as f32 * self.scaling_factor)
+    Ok(self.read()? as f32 * self.scaling_factor)
+  }
+
+  /// Reads the voltage at the ADC input in volts, with the calibration
+  /// applied.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let sensor = ADC::new(&Board::new(), AIN_0, 1.0).unwrap();
+  /// println!("{} V", sensor.read_voltage().unwrap());
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if the ADC isn't enabled.
+  pub fn read_voltage(&self) -> Result<f32> {
+    Ok(self.to_voltage(self.read()? as f32))
+  }
+
+  /// Converts a raw reading, such as an average of several, to volts with the
+  /// calibration applied.
+  pub fn to_voltage(&self, raw: f32) -> f32 {
+    self.calibration.apply(raw * REFERENCE_VOLTAGE / FULL_SCALE as f32)
+  }
+}
+
+/// Reads and parses an `in_voltageN_raw` attribute, retrying while the driver
+/// answers `EAGAIN` because a conversion didn't complete in time.
+fn read_raw(path: &Path, device: &str) -> Result<u32> {
+  let mut attempts = 1;
+  let value_str = loop {
+    match fs::read_to_string(path) {
+      Ok(value_str) => break value_str,
+      Err(ref e) if e.kind() == io::ErrorKind::WouldBlock && attempts < READ_ATTEMPTS => {
+        attempts += 1;
+        thread::sleep(Duration::from_millis(1));
+      }
+      Err(e) => return Err(Error::from_io(e, device, path)),
+    }
+  };
+  match value_str.trim().parse() {
+    Ok(value) => Ok(value),
+    Err(_) => bail!(ErrorKind::InvalidValue(device.to_string(), value_str.trim().to_string())),
   }
 }
@@ -0,0 +1,464 @@
+//! The buffered ADC module.
+//!
+//! `ADC::read()` opens, reads and parses a sysfs file for every sample, which
+//! limits it to a few hundred samples per second.
+//! In continuous mode, the ADC instead samples a set of channels on its own
+//! and the kernel queues the results in a buffer, which is read in bulk from
+//! `/dev/iio:device0`.
+//!
+//! Each scan holds one sample of every enabled channel.
+//! If the buffer fills up because it isn't read fast enough, new scans are
+//! dropped; reading reports this as an `Overrun` error once, then carries on
+//! with the scans that were kept.
+//!
+//! `ADCBuffer` returns scans as plain lists of samples, while `ADCScan` returns
+//! them as `Frame`s keyed by channel, optionally stamped with the time the
+//! scan was taken.
+//! As every channel of a scan is sampled in one pass of the ADC's sequencer,
+//! the samples in a frame were taken within microseconds of each other.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libbeaglebone::adc_buffer::ADCBuffer;
+//! use libbeaglebone::prelude::*;
+//!
+//! let mut buffer = ADCBuffer::new(&Board::new(), &[AIN_0, AIN_2]).unwrap();
+//! buffer.set_length(1000).unwrap();
+//! buffer.start().unwrap();
+//! let samples = buffer.read_scan().unwrap();
+//! println!("AIN0: {}, AIN2: {}", samples[0], samples[1]);
+//! ```
+
+use board::Board;
+use errors::*;
+use pins::Pin;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use util::*;
+
+/// The number of ADC channels.
+const CHANNELS: u8 = 8;
+
+/// The layout of a channel's samples in a scan, as described by its
+/// `scan_elements/in_voltageN_type` attribute, e.g. `le:u12/16>>0`.
+#[derive(Debug, Clone, Copy)]
+struct ChannelFormat {
+  big_endian: bool,
+  real_bits: u32,
+  storage_bytes: usize,
+  shift: u32,
+}
+
+impl ChannelFormat {
+  /// Parses a scan element type attribute.
+  fn parse(device: &str, s: &str) -> Result<ChannelFormat> {
+    let invalid = || ErrorKind::InvalidValue(device.to_string(), s.to_string());
+    let s = s.trim();
+    let (endianness, rest) = s.split_at(s.find(':').ok_or_else(invalid)?);
+    // Skip the ':' and the sign; samples are unsigned and the timestamp never
+    // has its top bit set
+    let rest = rest.get(2..).ok_or_else(invalid)?;
+    let slash = rest.find('/').ok_or_else(invalid)?;
+    let shift_at = rest.find(">>").ok_or_else(invalid)?;
+    let real_bits = rest[..slash].parse::<u32>().map_err(|_| invalid())?;
+    let storage_bits = rest[slash + 1..shift_at].parse::<usize>().map_err(|_| invalid())?;
+    let shift = rest[shift_at + 2..].parse::<u32>().map_err(|_| invalid())?;
+    if storage_bits % 8 != 0 || storage_bits == 0 || storage_bits > 64 {
+      bail!(invalid());
+    }
+    Ok(ChannelFormat {
+      big_endian: endianness == "be",
+      real_bits: real_bits,
+      storage_bytes: storage_bits / 8,
+      shift: shift,
+    })
+  }
+
+  /// Decodes a sample from its bytes.
+  fn decode(&self, bytes: &[u8]) -> u64 {
+    let raw = if self.big_endian {
+      bytes.iter().fold(0u64, |value, &byte| value << 8 | byte as u64)
+    } else {
+      bytes.iter().rev().fold(0u64, |value, &byte| value << 8 | byte as u64)
+    };
+    let mask = if self.real_bits >= 64 { !0 } else { (1u64 << self.real_bits) - 1 };
+    (raw >> self.shift) & mask
+  }
+}
+
+/// Represents a set of ADC channels sampled continuously into the kernel's
+/// buffer.
+///
+/// The buffer is stopped when dropped.
+#[derive(Debug)]
+pub struct ADCBuffer {
+  device_path: PathBuf,
+  dev_path: PathBuf,
+  dev_file: Option<File>,
+  channels: Vec<u8>,
+  formats: Vec<(usize, ChannelFormat)>,
+  timestamp: Option<(usize, ChannelFormat)>,
+  scan_bytes: usize,
+  length: usize,
+  data: Vec<u8>,
+  pos: usize,
+  overruns: u64,
+}
+
+impl ADCBuffer {
+  /// Creates a new buffer scanning the ADC inputs on `pins`.
+  ///
+  /// Only these channels are enabled in the scan; the buffer isn't started
+  /// until `start()` is called.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidPin` if a pin isn't one of the analog inputs, or if
+  /// there are no pins.
+  /// Fails if the ADC or its buffer isn't available, e.g. because the ADC
+  /// overlay isn't loaded.
+  pub fn new(board: &Board, pins: &[Pin]) -> Result<ADCBuffer> {
+    let mut channels = Vec::new();
+    for &pin in pins {
+      match pin.adc_channel() {
+        Some(channel) => channels.push(channel),
+        None => bail!(ErrorKind::InvalidPin(pin.to_string())),
+      }
+    }
+    if channels.is_empty() {
+      bail!(ErrorKind::InvalidPin("no ADC pins to scan".to_string()));
+    }
+    channels.sort();
+    channels.dedup();
+
+    let mut buffer = ADCBuffer {
+      device_path: board.sysfs_path("bus/iio/devices/iio:device0"),
+      dev_path: board.devfs_path("iio:device0"),
+      dev_file: None,
+      channels: channels,
+      formats: Vec::new(),
+      timestamp: None,
+      scan_bytes: 0,
+      length: 0,
+      data: Vec::new(),
+      pos: 0,
+      overruns: 0,
+    };
+    // The scan can only be changed while the buffer is stopped
+    buffer.stop()?;
+    buffer.set_scan(false)?;
+    let length_path = buffer.device_path.join("buffer/length");
+    if length_path.exists() {
+      buffer.length = parse_file(&length_path, &buffer.name())?;
+    }
+    Ok(buffer)
+  }
+
+  /// Returns the channels in each scan, in the order of their samples.
+  pub fn channels(&self) -> &[u8] {
+    &self.channels
+  }
+
+  /// Sets the number of scans the kernel's buffer holds.
+  ///
+  /// A longer buffer tolerates longer gaps between reads before overrunning.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the buffer is running, or if the kernel rejects the length.
+  pub fn set_length(&mut self, scans: usize) -> Result<()> {
+    self.device_path.join("buffer/length").write_file(&self.name(), &scans.to_string())?;
+    self.length = scans;
+    Ok(())
+  }
+
+  /// Sets the rate at which the ADC scans its channels, in Hz.
+  ///
+  /// Only kernels whose ADC driver has a `sampling_frequency` attribute
+  /// support this; others set the rate through the device tree.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the driver doesn't support setting the rate, or rejects it.
+  pub fn set_sampling_frequency(&mut self, frequency_hz: u32) -> Result<()> {
+    self.device_path.join("sampling_frequency").write_file(&self.name(),
+                                                           &frequency_hz.to_string())
+  }
+
+  /// Starts sampling into the buffer.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the buffer can't be enabled or `/dev/iio:device0` can't be
+  /// opened.
+  pub fn start(&mut self) -> Result<()> {
+    self.device_path.join("buffer/enable").write_file(&self.name(), "1")?;
+    let file = File::open(&self.dev_path)
+      .map_err(|e| Error::from_io(e, &self.name(), &self.dev_path))?;
+    self.dev_file = Some(file);
+    self.data.clear();
+    self.pos = 0;
+    Ok(())
+  }
+
+  /// Stops sampling, discarding any scans left in the buffer.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the buffer can't be disabled.
+  pub fn stop(&mut self) -> Result<()> {
+    self.dev_file = None;
+    self.device_path.join("buffer/enable").write_file(&self.name(), "0")
+  }
+
+  /// Returns the number of times samples were lost since the buffer was
+  /// created.
+  pub fn overruns(&self) -> u64 {
+    self.overruns
+  }
+
+  /// Reads the next scan, blocking until one is available.
+  ///
+  /// The samples are raw 12-bit counts, in the order of `channels()`.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `Overrun` once if the buffer filled up before it was read,
+  /// so scans were lost; the next call returns the scans that were kept.
+  /// Fails if the buffer isn't started or can't be read.
+  pub fn read_scan(&mut self) -> Result<Vec<u16>> {
+    self.next_scan().map(|(samples, _)| samples)
+  }
+
+  /// Reads the next scan's samples and timestamp, if it has one.
+  fn next_scan(&mut self) -> Result<(Vec<u16>, Option<i64>)> {
+    if self.pos + self.scan_bytes > self.data.len() {
+      self.fill()?;
+    }
+    let scan = &self.data[self.pos..self.pos + self.scan_bytes];
+    let decode = |&(offset, format): &(usize, ChannelFormat)| {
+      format.decode(&scan[offset..offset + format.storage_bytes])
+    };
+    let samples = self.formats.iter().map(|sample| decode(sample) as u16).collect();
+    let timestamp = self.timestamp.as_ref().map(|timestamp| decode(timestamp) as i64);
+    self.pos += self.scan_bytes;
+    Ok((samples, timestamp))
+  }
+
+  /// Enables the selected channels and, if `timestamp` is set, the timestamp,
+  /// disables the others and works out the layout of a scan.
+  fn set_scan(&mut self, timestamp: bool) -> Result<()> {
+    let mut formats = Vec::new();
+    for channel in 0..CHANNELS {
+      let en_path = self.scan_path(&format!("in_voltage{}_en", channel));
+      let enabled = self.channels.contains(&channel);
+      if enabled {
+        en_path.write_file(&self.name(), "1")?;
+        let type_path = self.scan_path(&format!("in_voltage{}_type", channel));
+        let format = ChannelFormat::parse(&self.name(), &type_path.read_file(&self.name())?)?;
+        formats.push(format);
+      } else if en_path.exists() {
+        en_path.write_file(&self.name(), "0")?;
+      }
+    }
+    let timestamp_format = if timestamp {
+      self.scan_path("in_timestamp_en").write_file(&self.name(), "1")?;
+      let type_path = self.scan_path("in_timestamp_type");
+      Some(ChannelFormat::parse(&self.name(), &type_path.read_file(&self.name())?)?)
+    } else {
+      let en_path = self.scan_path("in_timestamp_en");
+      if en_path.exists() {
+        en_path.write_file(&self.name(), "0")?;
+      }
+      None
+    };
+
+    // Samples are aligned to their own size within a scan, and the scan to
+    // its largest sample
+    let mut offset = 0;
+    self.formats = formats.into_iter()
+                          .map(|format| {
+                            offset = align(offset, format.storage_bytes);
+                            let sample = (offset, format);
+                            offset += format.storage_bytes;
+                            sample
+                          })
+                          .collect();
+    // The timestamp always comes last, as it has the highest scan index
+    self.timestamp = timestamp_format.map(|format| {
+      offset = align(offset, format.storage_bytes);
+      let timestamp = (offset, format);
+      offset += format.storage_bytes;
+      timestamp
+    });
+    let largest = self.formats
+                      .iter()
+                      .chain(self.timestamp.iter())
+                      .map(|&(_, f)| f.storage_bytes)
+                      .max()
+                      .unwrap_or(1);
+    self.scan_bytes = align(offset, largest);
+    Ok(())
+  }
+
+  /// Reads as many whole scans as are available into `data`.
+  fn fill(&mut self) -> Result<()> {
+    // A full buffer means the kernel has had to drop new scans
+    let available_path = self.device_path.join("buffer/data_available");
+    let overrun = self.length > 0 && available_path.exists() &&
+                  parse_file::<usize>(&available_path, &self.name())? >= self.length;
+
+    let scan_bytes = self.scan_bytes;
+    let capacity = scan_bytes * self.length.max(1);
+    self.data.resize(capacity, 0);
+    self.pos = 0;
+    let read = {
+      let file = match self.dev_file {
+        Some(ref mut file) => file,
+        None => bail!(ErrorKind::NotExported(self.name(), self.dev_path.clone())),
+      };
+      loop {
+        match file.read(&mut self.data) {
+          Ok(read) => break read,
+          // Retry if a signal interrupted the read, or the driver was busy
+          Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+          Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+            thread::sleep(Duration::from_millis(1));
+          }
+          Err(e) => return Err(Error::from_io(e, &self.name(), &self.dev_path)),
+        }
+      }
+    };
+    self.data.truncate(read - read % scan_bytes);
+    if self.data.is_empty() {
+      bail!(ErrorKind::Io(self.name(), self.dev_path.clone()));
+    }
+
+    if overrun {
+      self.overruns += 1;
+      bail!(ErrorKind::Overrun(self.name()));
+    }
+    Ok(())
+  }
+
+  /// Returns the path of a scan element attribute.
+  fn scan_path(&self, attribute: &str) -> PathBuf {
+    self.device_path.join("scan_elements").join(attribute)
+  }
+
+  fn name(&self) -> String {
+    "ADC buffer".to_string()
+  }
+}
+
+/// One scan of a set of ADC channels.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+  /// The raw 12-bit samples, keyed by channel number.
+  pub samples: BTreeMap<u8, u16>,
+  /// The time the scan was taken in nanoseconds, from the clock named by the
+  /// ADC's `current_timestamp_clock` attribute (`CLOCK_REALTIME` by default),
+  /// if timestamps are enabled.
+  pub timestamp_ns: Option<i64>,
+}
+
+impl Frame {
+  /// Returns the sample of the ADC input on a pin, if it was scanned.
+  pub fn sample(&self, pin: Pin) -> Option<u16> {
+    pin.adc_channel().and_then(|channel| self.samples.get(&channel).cloned())
+  }
+}
+
+/// Represents a set of ADC channels read together as frames.
+///
+/// The channels are sampled continuously through the kernel's buffer, which
+/// is stopped when the scan is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use libbeaglebone::adc_buffer::ADCScan;
+/// use libbeaglebone::prelude::*;
+///
+/// let mut monitor = ADCScan::new(&Board::new(), &[AIN_1, AIN_3], true).unwrap();
+/// let frame = monitor.read().unwrap();
+/// println!("AIN1: {:?} at {:?} ns", frame.sample(AIN_1), frame.timestamp_ns);
+/// ```
+#[derive(Debug)]
+pub struct ADCScan {
+  buffer: ADCBuffer,
+}
+
+impl ADCScan {
+  /// Creates a new scan of the ADC inputs on `pins` and starts sampling.
+  ///
+  /// If `timestamp` is set, each frame is stamped with the time it was
+  /// taken.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidPin` if a pin isn't one of the analog inputs, or if
+  /// there are no pins.
+  /// Fails if the ADC or its buffer isn't available, or if the buffer can't
+  /// be started.
+  pub fn new(board: &Board, pins: &[Pin], timestamp: bool) -> Result<ADCScan> {
+    let mut buffer = ADCBuffer::new(board, pins)?;
+    if timestamp {
+      buffer.set_scan(true)?;
+    }
+    buffer.start()?;
+    Ok(ADCScan { buffer: buffer })
+  }
+
+  /// Returns the channels in each frame.
+  pub fn channels(&self) -> &[u8] {
+    self.buffer.channels()
+  }
+
+  /// Returns the number of times frames were lost since the scan was
+  /// created.
+  pub fn overruns(&self) -> u64 {
+    self.buffer.overruns()
+  }
+
+  /// Reads the next frame, blocking until one is available.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `Overrun` once if frames were lost because they weren't read
+  /// fast enough.
+  /// Fails if the buffer can't be read.
+  pub fn read(&mut self) -> Result<Frame> {
+    let (samples, timestamp) = self.buffer.next_scan()?;
+    Ok(Frame {
+      samples: self.buffer.channels.iter().cloned().zip(samples).collect(),
+      timestamp_ns: timestamp,
+    })
+  }
+}
+
+impl Iterator for ADCBuffer {
+  type Item = Result<Vec<u16>>;
+
+  /// Reads the next scan; see `read_scan()`.
+  fn next(&mut self) -> Option<Result<Vec<u16>>> {
+    Some(self.read_scan())
+  }
+}
+
+impl Drop for ADCBuffer {
+  fn drop(&mut self) {
+    // A buffer left enabled keeps its channels from being read through sysfs
+    let _ = self.stop();
+  }
+}
+
+/// Rounds `offset` up to a multiple of `size`.
+fn align(offset: usize, size: usize) -> usize {
+  offset.div_ceil(size) * size
+}
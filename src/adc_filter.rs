@@ -0,0 +1,223 @@
+//! The ADC filter module.
+//!
+//! Analog sensors are noisy, so a single `ADC::read()` is rarely what's
+//! wanted.
+//! `FilteredADC` wraps an `ADC` and smooths its readings with a `Filter`:
+//!
+//! - `Oversample` and `Median` take several fresh samples for every reading,
+//!   trading read time for noise.
+//! - `MovingAverage` and `Exponential` take one sample per reading and
+//!   average it with the previous ones, trading response time for noise.
+//!
+//! Every reading comes with the variance of the samples behind it.
+//! A sensor whose variance suddenly rises is often failing, e.g. because of a
+//! loose connection.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libbeaglebone::adc_filter::{Filter, FilteredADC};
+//! use libbeaglebone::prelude::*;
+//!
+//! let adc = ADC::new(&Board::new(), AIN_0, 1.0).unwrap();
+//! let mut sensor = FilteredADC::new(adc, Filter::MovingAverage(4)).unwrap();
+//! let reading = sensor.read().unwrap();
+//! println!("{} (variance {})", reading.value, reading.variance);
+//! ```
+
+use adc::ADC;
+use errors::*;
+use std::collections::VecDeque;
+
+/// How a `FilteredADC` smooths its readings.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Filter {
+  /// Averages N fresh samples per reading.
+  Oversample(usize),
+  /// Averages the last N samples, taking one per reading.
+  MovingAverage(usize),
+  /// Averages all samples, weighting each new one by a smoothing factor
+  /// between 0.0 (ignore new samples) and 1.0 (no smoothing), taking one per
+  /// reading.
+  Exponential(f32),
+  /// Takes the median of N fresh samples per reading, which rejects spikes
+  /// completely as long as fewer than half the samples are spikes.
+  Median(usize),
+}
+
+/// A filtered ADC reading, in raw counts.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Reading {
+  /// The filtered value.
+  pub value: f32,
+  /// The variance of the samples behind the value, in counts squared.
+  pub variance: f32,
+}
+
+impl Reading {
+  /// Returns the standard deviation of the samples behind the value, in
+  /// counts.
+  pub fn std_dev(&self) -> f32 {
+    self.variance.sqrt()
+  }
+}
+
+/// Represents an ADC whose readings are filtered.
+#[derive(Debug)]
+pub struct FilteredADC {
+  adc: ADC,
+  filter: Filter,
+  history: VecDeque<f32>,
+  average: Option<Reading>,
+}
+
+impl FilteredADC {
+  /// Creates a new filtered ADC.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if a filter's sample count is 0, or if the
+  /// exponential smoothing factor isn't greater than 0.0 and at most 1.0.
+  pub fn new(adc: ADC, filter: Filter) -> Result<FilteredADC> {
+    let valid = match filter {
+      Filter::Oversample(n) |
+      Filter::MovingAverage(n) |
+      Filter::Median(n) => n > 0,
+      Filter::Exponential(alpha) => alpha > 0.0 && alpha <= 1.0,
+    };
+    if !valid {
+      bail!(ErrorKind::InvalidValue("ADC filter".to_string(), format!("{:?}", filter)));
+    }
+    Ok(FilteredADC {
+      adc: adc,
+      filter: filter,
+      history: VecDeque::new(),
+      average: None,
+    })
+  }
+
+  /// Returns the filter applied to the readings.
+  pub fn filter(&self) -> Filter {
+    self.filter
+  }
+
+  /// Takes a filtered reading.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::adc_filter::{Filter, FilteredADC};
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let adc = ADC::new(&Board::new(), AIN_2, 1.0).unwrap();
+  /// let mut sensor = FilteredADC::new(adc, Filter::Median(5)).unwrap();
+  ///
+  /// let reading = sensor.read().unwrap();
+  /// if reading.std_dev() > 50.0 {
+  ///   println!("Sensor on AIN2 is unusually noisy, check its wiring");
+  /// }
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if the ADC can't be read.
+  pub fn read(&mut self) -> Result<Reading> {
+    match self.filter {
+      Filter::Oversample(n) => {
+        let samples = self.take_samples(n)?;
+        let mean = mean(&samples);
+        Ok(Reading {
+          value: mean,
+          variance: variance(&samples, mean),
+        })
+      }
+      Filter::Median(n) => {
+        let mut samples = self.take_samples(n)?;
+        samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let middle = samples.len() / 2;
+        let median = if samples.len() % 2 == 0 {
+          (samples[middle - 1] + samples[middle]) / 2.0
+        } else {
+          samples[middle]
+        };
+        Ok(Reading {
+          value: median,
+          variance: variance(&samples, mean(&samples)),
+        })
+      }
+      Filter::MovingAverage(n) => {
+        let sample = self.adc.read()? as f32;
+        if self.history.len() == n {
+          let _ = self.history.pop_front();
+        }
+        self.history.push_back(sample);
+        let samples: Vec<f32> = self.history.iter().cloned().collect();
+        let mean = mean(&samples);
+        Ok(Reading {
+          value: mean,
+          variance: variance(&samples, mean),
+        })
+      }
+      Filter::Exponential(alpha) => {
+        let sample = self.adc.read()? as f32;
+        // The exponentially weighted mean and variance, updated incrementally
+        let average = match self.average {
+          Some(average) => {
+            let diff = sample - average.value;
+            Reading {
+              value: average.value + alpha * diff,
+              variance: (1.0 - alpha) * (average.variance + alpha * diff * diff),
+            }
+          }
+          None => {
+            Reading {
+              value: sample,
+              variance: 0.0,
+            }
+          }
+        };
+        self.average = Some(average);
+        Ok(average)
+      }
+    }
+  }
+
+  /// Takes a filtered reading and converts it to volts, with the ADC's
+  /// calibration applied.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the ADC can't be read.
+  pub fn read_voltage(&mut self) -> Result<f32> {
+    let reading = self.read()?;
+    Ok(self.adc.to_voltage(reading.value))
+  }
+
+  /// Forgets the samples averaged so far, e.g. after the sensor was
+  /// replaced.
+  pub fn reset(&mut self) {
+    self.history.clear();
+    self.average = None;
+  }
+
+  /// Returns the ADC being filtered.
+  pub fn into_adc(self) -> ADC {
+    self.adc
+  }
+
+  /// Reads `n` fresh samples.
+  fn take_samples(&self, n: usize) -> Result<Vec<f32>> {
+    (0..n).map(|_| self.adc.read().map(|sample| sample as f32)).collect()
+  }
+}
+
+/// Returns the mean of some samples.
+fn mean(samples: &[f32]) -> f32 {
+  samples.iter().sum::<f32>() / samples.len() as f32
+}
+
+/// Returns the (population) variance of some samples about their mean.
+fn variance(samples: &[f32], mean: f32) -> f32 {
+  samples.iter().map(|sample| (sample - mean) * (sample - mean)).sum::<f32>() /
+  samples.len() as f32
+}
@@ -0,0 +1,153 @@
+//! The board module.
+//!
+//! Every peripheral in this crate talks to the kernel through files under
+//! sysfs (`/sys`) and devfs (`/dev`).
+//! A `Board` carries the locations of those two trees and is handed to each
+//! peripheral's constructor.
+//!
+//! On a real BeagleBone you'll want `Board::new()`, which uses the standard
+//! locations.
+//! Pointing the roots at another directory lets the peripherals run against a
+//! tree of fake attribute files, which is handy for testing without hardware.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libbeaglebone::prelude::*;
+//!
+//! // Every peripheral created with this board uses the fake tree, so P8.11
+//! // reads /tmp/fake/sys/class/gpio/gpio45/value.
+//! let board = Board::with_roots("/tmp/fake/sys", "/tmp/fake/dev");
+//! let pin = GPIO::new(&board, P8_11).unwrap().into_input().unwrap();
+//! println!("{:?}", pin.read().unwrap());
+//! ```
+
+use gpio::GPIOBackend;
+use std::path::{Path, PathBuf};
+
+/// Represents the BeagleBone that peripherals are created on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+  sysfs_root: PathBuf,
+  devfs_root: PathBuf,
+  gpio_backend: GPIOBackend,
+  auto_pinmux: bool,
+}
+
+impl Board {
+  /// Creates a new board using the standard `/sys` and `/dev` roots.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let board = Board::new();
+  /// assert_eq!(board.sysfs_root().to_str(), Some("/sys"));
+  /// ```
+  pub fn new() -> Board {
+    Board::with_roots("/sys", "/dev")
+  }
+
+  /// Creates a new board using custom sysfs and devfs roots.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// // Point the board at a fake sysfs/devfs tree.
+  /// let board = Board::with_roots("/tmp/fake/sys", "/tmp/fake/dev");
+  /// assert_eq!(
+  ///   board.sysfs_path("class/gpio/export").to_str(),
+  ///   Some("/tmp/fake/sys/class/gpio/export")
+  /// );
+  /// ```
+  pub fn with_roots<P: AsRef<Path>, Q: AsRef<Path>>(sysfs_root: P,
+                                                    devfs_root: Q)
+                                                    -> Board {
+    Board {
+      sysfs_root: sysfs_root.as_ref().to_path_buf(),
+      devfs_root: devfs_root.as_ref().to_path_buf(),
+      gpio_backend: GPIOBackend::Sysfs,
+      auto_pinmux: false,
+    }
+  }
+
+  /// Returns the root of the sysfs tree.
+  pub fn sysfs_root(&self) -> &Path {
+    &self.sysfs_root
+  }
+
+  /// Returns the root of the devfs tree.
+  pub fn devfs_root(&self) -> &Path {
+    &self.devfs_root
+  }
+
+  /// Returns the kernel interface used by GPIO pins created on this board.
+  pub fn gpio_backend(&self) -> GPIOBackend {
+    self.gpio_backend
+  }
+
+  /// Sets the kernel interface used by GPIO pins created on this board.
+  ///
+  /// The default is `GPIOBackend::Sysfs`.
+  /// Pins that were already created keep the backend they were created with.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// // Use the /dev/gpiochipN character devices for GPIO pins.
+  /// let mut board = Board::new();
+  /// board.set_gpio_backend(GPIOBackend::CharDev);
+  /// ```
+  pub fn set_gpio_backend(&mut self, backend: GPIOBackend) {
+    self.gpio_backend = backend;
+  }
+
+  /// Returns whether peripherals created on this board mux their pins.
+  pub fn auto_pinmux(&self) -> bool {
+    self.auto_pinmux
+  }
+
+  /// Sets whether peripherals created on this board mux their pins.
+  ///
+  /// When enabled, the constructors of `GPIO`, `GPIOGroup`, `I2C`, `SPI`,
+  /// `UART` and `EQEP`, as well as `PWM::from_pin()` and `ECAP::from_pin()`,
+  /// set their pins' modes through cape-universal (see `pinmux`), so there's
+  /// no need to run `config-pin` beforehand.
+  /// The default is `false`, which leaves the pins as they are.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut board = Board::new();
+  /// board.set_auto_pinmux(true);
+  ///
+  /// // P9.12 is muxed as a GPIO before being used
+  /// let pin = GPIO::new(&board, P9_12).unwrap();
+  /// ```
+  pub fn set_auto_pinmux(&mut self, auto_pinmux: bool) {
+    self.auto_pinmux = auto_pinmux;
+  }
+
+  /// Returns the full path of a file relative to the sysfs root.
+  pub fn sysfs_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+    self.sysfs_root.join(path)
+  }
+
+  /// Returns the full path of a file relative to the devfs root.
+  pub fn devfs_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+    self.devfs_root.join(path)
+  }
+}
+
+impl Default for Board {
+  fn default() -> Board {
+    Board::new()
+  }
+}
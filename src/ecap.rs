@@ -0,0 +1,233 @@
+//! The eCAP module.
+//!
+//! The AM335x has three enhanced capture (eCAP) modules, two of which are
+//! broken out: eCAP0 on P9.42 and eCAP2 on P9.28.
+//! Besides generating a PWM signal (see `pwm`), an eCAP can timestamp the
+//! edges of an external signal, which is how `ECAP` measures the frequency,
+//! period and duty cycle of e.g. a tachometer or an RC receiver.
+//!
+//! Input capture needs the `ti-ecap-capture` counter driver (Linux 5.18 and
+//! later), which exposes each eCAP as `/sys/bus/counter/devices/counterN`.
+//! An eCAP can't capture input while it's exported as a PWM.
+//!
+//! The eCAP timestamps four edges in a loop: rising, falling, rising and
+//! falling.
+//! A measurement is taken from the latest of those, so it describes a single
+//! period of the signal.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libbeaglebone::ecap::ECAP;
+//! use libbeaglebone::prelude::*;
+//!
+//! let ecap = ECAP::from_pin(&Board::new(), P9_42).unwrap();
+//! let capture = ecap.measure().unwrap();
+//! println!("{} Hz, {}% duty", capture.frequency_hz(), capture.duty_fraction() * 100.0);
+//! ```
+
+use board::Board;
+use errors::*;
+use pinmux::{self, PinMode};
+use pins::Pin;
+use std::fs;
+use std::path::PathBuf;
+use util::*;
+
+/// The device tree node addresses of the eCAP modules.
+const ECAP_ADDRESSES: [&str; 3] = ["48300100", "48302100", "48304100"];
+
+/// The frequency of the eCAP clock, used when the driver doesn't report it.
+const DEFAULT_CLOCK_HZ: u64 = 100_000_000;
+
+/// A measurement of one period of a signal.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Capture {
+  /// The time between two rising edges in nanoseconds.
+  pub period_ns: u64,
+  /// The time the signal was high during the period in nanoseconds.
+  pub high_ns: u64,
+}
+
+impl Capture {
+  /// Returns the frequency of the signal in Hz.
+  pub fn frequency_hz(&self) -> f64 {
+    1e9 / self.period_ns as f64
+  }
+
+  /// Returns the fraction of the period the signal was high, from 0.0 to
+  /// 1.0.
+  pub fn duty_fraction(&self) -> f64 {
+    self.high_ns as f64 / self.period_ns as f64
+  }
+}
+
+/// Represents an eCAP module capturing an input signal.
+///
+/// Capturing stops when the handle is dropped.
+#[derive(Debug)]
+pub struct ECAP {
+  ecap_num: u8,
+  counter_path: PathBuf,
+  clock_hz: u64,
+}
+
+impl ECAP {
+  /// Creates a new eCAP object and starts capturing.
+  ///
+  /// The counter device is found by the address of the eCAP's device tree
+  /// node, as counter numbers vary with the drivers loaded.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if `ecap_num` isn't 0, 1 or 2.
+  /// Fails with `NotExported` if the eCAP has no counter device, e.g.
+  /// because it's used as a PWM or the `ti-ecap-capture` driver isn't loaded.
+  pub fn new(board: &Board, ecap_num: u8) -> Result<ECAP> {
+    let address = match ECAP_ADDRESSES.get(ecap_num as usize) {
+      Some(address) => address,
+      None => bail!(ErrorKind::InvalidValue("eCAP".to_string(), ecap_num.to_string())),
+    };
+
+    let devices_path = board.sysfs_path("bus/counter/devices");
+    let entries = fs::read_dir(&devices_path)
+      .map_err(|e| Error::from_io(e, "counter devices", &devices_path))?;
+    for entry in entries {
+      let entry = entry.map_err(|e| Error::from_io(e, "counter devices", &devices_path))?;
+      if links_to_address(&entry.path(), address) {
+        let mut ecap = ECAP {
+          ecap_num: ecap_num,
+          counter_path: entry.path(),
+          clock_hz: DEFAULT_CLOCK_HZ,
+        };
+        ecap.start()?;
+        return Ok(ecap);
+      }
+    }
+    // No counter device means the eCAP is used as a PWM or its driver isn't
+    // loaded
+    bail!(ErrorKind::NotExported(format!("counter device for eCAP #{} ({})", ecap_num, address),
+                                 devices_path))
+  }
+
+  /// Creates a new eCAP object for the eCAP input on a header pin and starts
+  /// capturing.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidPin` if the pin has no eCAP input.
+  /// Fails with `NotExported` if the eCAP has no counter device.
+  /// Fails if the board muxes pins and the pin can't be muxed.
+  pub fn from_pin(board: &Board, pin: Pin) -> Result<ECAP> {
+    let ecap_num = pin.info()
+                      .modes
+                      .iter()
+                      .filter_map(|mode| {
+                        let mode = mode.to_lowercase();
+                        if mode.starts_with("ecap") && mode.contains("_in") {
+                          mode[4..5].parse::<u8>().ok()
+                        } else {
+                          None
+                        }
+                      })
+                      .next();
+    let ecap_num = match ecap_num {
+      Some(ecap_num) => ecap_num,
+      None => bail!(ErrorKind::InvalidPin(format!("{} has no eCAP input", pin))),
+    };
+    // The eCAP signal is muxed by the same state as its PWM output ("pwm2"
+    // for eCAP2 on P9.28)
+    if board.auto_pinmux() {
+      pinmux::set_mode(board, pin, PinMode::Pwm)?;
+    }
+    ECAP::new(board, ecap_num)
+  }
+
+  /// Measures the latest complete period of the input signal.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::ecap::ECAP;
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// // A fan tachometer giving two pulses per revolution
+  /// let tach = ECAP::from_pin(&Board::new(), P9_42).unwrap();
+  /// match tach.measure() {
+  ///   Ok(capture) => println!("{:.0} RPM", capture.frequency_hz() * 60.0 / 2.0),
+  ///   Err(_) => println!("Fan stopped"),
+  /// }
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `Timeout` if the signal hasn't had an edge for two periods,
+  /// e.g. because it stopped or is stuck high or low.
+  /// Fails if the counter can't be read.
+  pub fn measure(&self) -> Result<Capture> {
+    let mut captures = [0u32; 4];
+    for (i, capture) in captures.iter_mut().enumerate() {
+      // The hardware counter is 32 bits wide, and wraps around
+      *capture = self.read_count(&format!("count0/capture{}", i))? as u32;
+    }
+    let now = self.read_count("count0/count")? as u32;
+
+    // Find the latest edge, then work back to the rising edge a whole period
+    // before it
+    let latest = (0..4).min_by_key(|&i| now.wrapping_sub(captures[i])).unwrap();
+    let rise = if latest % 2 == 0 { latest } else { latest - 1 };
+    let prev_rise = (rise + 2) % 4;
+    let fall = rise + 1;
+
+    let period = captures[rise].wrapping_sub(captures[prev_rise]);
+    let high = if latest == fall {
+      captures[fall].wrapping_sub(captures[rise])
+    } else {
+      captures[(prev_rise + 1) % 4].wrapping_sub(captures[prev_rise])
+    };
+    let age = now.wrapping_sub(captures[latest]);
+    if period == 0 || high > period || age as u64 > 2 * period as u64 {
+      bail!(ErrorKind::Timeout(self.name()));
+    }
+
+    Ok(Capture {
+      period_ns: self.ticks_to_ns(period),
+      high_ns: self.ticks_to_ns(high),
+    })
+  }
+
+  /// Sets the capture edges and enables the counter.
+  fn start(&mut self) -> Result<()> {
+    let frequency_path = self.counter_path.join("signal0/frequency");
+    if frequency_path.exists() {
+      self.clock_hz = parse_file(&frequency_path, &self.name())?;
+    }
+    for (i, polarity) in ["positive", "negative", "positive", "negative"].iter().enumerate() {
+      self.counter_path
+          .join(format!("signal1/polarity{}", i))
+          .write_file(&self.name(), polarity)?;
+    }
+    self.counter_path.join("count0/enable").write_file(&self.name(), "1")
+  }
+
+  /// Reads a counter attribute.
+  fn read_count(&self, attribute: &str) -> Result<u64> {
+    parse_file(&self.counter_path.join(attribute), &self.name())
+  }
+
+  /// Converts eCAP clock ticks to nanoseconds.
+  fn ticks_to_ns(&self, ticks: u32) -> u64 {
+    ticks as u64 * 1_000_000_000 / self.clock_hz
+  }
+
+  fn name(&self) -> String {
+    format!("eCAP #{}", self.ecap_num)
+  }
+}
+
+impl Drop for ECAP {
+  fn drop(&mut self) {
+    // Stop the counter start() enabled
+    let _ = self.counter_path.join("count0/enable").write_file(&self.name(), "0");
+  }
+}
@@ -8,3 +8,19 @@ pub enum DeviceState {
   /// Not exported and unavailable for use.
   Unexported,
 }
+
+/// What a GPIO or PWM handle does to its device when it's dropped.
+///
+/// Handles only touch devices they exported themselves.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SafeState {
+  /// Leave the device configured as it is.
+  Leave,
+  /// Unexport the device. PWMs are disabled first.
+  Unexport,
+  /// Drive GPIO outputs low, then unexport. PWMs behave as with `Unexport`.
+  DriveLow,
+  /// Stop driving the device but leave it exported: GPIO outputs become
+  /// inputs and PWMs are disabled.
+  Disable,
+}
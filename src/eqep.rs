@@ -0,0 +1,228 @@
+//! The eQEP module.
+//!
+//! The BeagleBone Black has 3 enhanced quadrature encoder pulse (eQEP) units,
+//! which count the edges of a quadrature encoder's A and B signals in
+//! hardware, so no pulses are missed however busy the CPU is.
+//!
+//! The units are driven by the `tieqep` driver, which exposes them under
+//! `/sys/devices/platform/ocp/4830X000.epwmss/4830X180.eqep`.
+//! Their inputs need to be configured by using the `config-pin` command, e.g.
+//! `config-pin P8.11 qep` and `config-pin P8.12 qep` for eQEP2, or by
+//! enabling `Board::set_auto_pinmux()`.
+//!
+//! | Unit  | A input | B input |
+//! |-------|---------|---------|
+//! | eQEP0 | P9.42   | P9.27   |
+//! | eQEP1 | P8.35   | P8.33   |
+//! | eQEP2 | P8.12   | P8.11   |
+//!
+//! eQEP0's A input is on P9.42's second ball, which `pins` doesn't describe,
+//! so it's never muxed automatically.
+//! eQEP1's inputs are reserved by HDMI.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libbeaglebone::eqep::{EQEP, EQEPMode};
+//! use libbeaglebone::prelude::*;
+//!
+//! let mut encoder = EQEP::new(&Board::new(), 2).unwrap();
+//! println!("{}", encoder.read().unwrap());
+//!
+//! // Count from zero again
+//! encoder.reset().unwrap();
+//!
+//! // Latch the count every 10 ms, for measuring speed
+//! encoder.set_mode(EQEPMode::Relative).unwrap();
+//! encoder.set_period(10_000_000).unwrap();
+//! ```
+
+use board::Board;
+use errors::*;
+use pinmux::{self, PinMode};
+use pins::Pin;
+use std::path::PathBuf;
+use util::*;
+
+/// How the eQEP reports its position.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EQEPMode {
+  /// The position is the count since the last reset.
+  Absolute,
+  /// The position is the count during the last unit timer period, i.e. a
+  /// speed.
+  Relative,
+}
+
+/// Represents an eQEP unit.
+#[derive(Debug)]
+pub struct EQEP {
+  eqep_num: u8,
+  eqep_path: PathBuf,
+}
+
+impl EQEP {
+  /// Creates a new eQEP object.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if `eqep_num` isn't 0, 1 or 2.
+  /// Fails if the board muxes pins and the inputs can't be muxed.
+  pub fn new(board: &Board, eqep_num: u8) -> Result<EQEP> {
+    if eqep_num > 2 {
+      bail!(ErrorKind::InvalidValue("eQEP".to_string(), eqep_num.to_string()));
+    }
+    if board.auto_pinmux() {
+      for &pin in eqep_pins(eqep_num) {
+        pinmux::set_mode(board, pin, PinMode::Eqep)?;
+      }
+    }
+    let base = 0x4830_0000 + 0x2000 * eqep_num as u32;
+    Ok(EQEP {
+      eqep_num: eqep_num,
+      eqep_path: board.sysfs_path(format!("devices/platform/ocp/{:x}.epwmss/{:x}.eqep",
+                                          base,
+                                          base + 0x180)),
+    })
+  }
+
+  /// Reads the position of the encoder in counts.
+  ///
+  /// Each encoder line gives four counts, one per edge of A and B.
+  /// In relative mode, this is the count during the last unit timer period.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::eqep::EQEP;
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let encoder = EQEP::new(&Board::new(), 2).unwrap();
+  /// println!("Position: {}", encoder.read().unwrap());
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn read(&self) -> Result<i32> {
+    parse_file(&self.eqep_path.join("position"), &self.name())
+  }
+
+  /// Sets the position of the encoder in counts.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn set_position(&mut self, position: i32) -> Result<()> {
+    self.eqep_path.join("position").write_file(&self.name(), &position.to_string())
+  }
+
+  /// Resets the position of the encoder to zero.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn reset(&mut self) -> Result<()> {
+    self.set_position(0)
+  }
+
+  /// Sets how the eQEP reports its position.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn set_mode(&mut self, mode: EQEPMode) -> Result<()> {
+    self.eqep_path.join("mode").write_file(&self.name(), match mode {
+      EQEPMode::Absolute => "0",
+      EQEPMode::Relative => "1",
+    })
+  }
+
+  /// Reads how the eQEP reports its position.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn mode(&self) -> Result<EQEPMode> {
+    match parse_file::<u8>(&self.eqep_path.join("mode"), &self.name())? {
+      0 => Ok(EQEPMode::Absolute),
+      1 => Ok(EQEPMode::Relative),
+      value => bail!(ErrorKind::InvalidValue(self.name(), value.to_string())),
+    }
+  }
+
+  /// Sets the period of the unit timer in nanoseconds.
+  ///
+  /// In relative mode, the position is latched and restarted every period;
+  /// 0 stops the timer.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn set_period(&mut self, period_ns: u64) -> Result<()> {
+    self.eqep_path.join("period").write_file(&self.name(), &period_ns.to_string())
+  }
+
+  /// Reads the period of the unit timer in nanoseconds.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn period(&self) -> Result<u64> {
+    parse_file(&self.eqep_path.join("period"), &self.name())
+  }
+
+  /// Reads the speed of the encoder in counts per second.
+  ///
+  /// This requires relative mode and a unit timer period.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::eqep::{EQEP, EQEPMode};
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut encoder = EQEP::new(&Board::new(), 2).unwrap();
+  /// encoder.set_mode(EQEPMode::Relative).unwrap();
+  /// encoder.set_period(10_000_000).unwrap();
+  ///
+  /// // A 500 line encoder gives 2000 counts per revolution
+  /// let rpm = encoder.read_velocity().unwrap() / 2000.0 * 60.0;
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the unit timer is stopped.
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn read_velocity(&self) -> Result<f64> {
+    let period_ns = self.period()?;
+    if period_ns == 0 {
+      bail!(ErrorKind::InvalidValue(self.name(), period_ns.to_string()));
+    }
+    Ok(self.read()? as f64 * 1e9 / period_ns as f64)
+  }
+
+  /// Enables or disables the eQEP.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the eQEP's driver isn't loaded.
+  pub fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+    self.eqep_path.join("enabled").write_file(&self.name(), if enabled { "1" } else { "0" })
+  }
+
+  fn name(&self) -> String {
+    format!("eQEP #{}", self.eqep_num)
+  }
+}
+
+/// Returns the header pins of an eQEP's inputs, which are muxed when the
+/// board muxes pins.
+fn eqep_pins(eqep_num: u8) -> &'static [Pin] {
+  match eqep_num {
+    0 => &[Pin::P9_27],
+    1 => &[Pin::P8_35, Pin::P8_33],
+    2 => &[Pin::P8_12, Pin::P8_11],
+    _ => &[],
+  }
+}
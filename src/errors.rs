@@ -1,3 +1,107 @@
 //! Error-handling setup using error-chain.
+//!
+//! Every error carries an `ErrorKind` describing what went wrong, along with
+//! the device and file involved, so callers can react programmatically:
+//!
+//! ```no_run
+//! use libbeaglebone::errors::ErrorKind;
+//! use libbeaglebone::prelude::*;
+//!
+//! let pin = GPIO::new(&Board::new(), P8_11).unwrap();
+//! match pin.into_input().and_then(|pin| pin.read()) {
+//!   Ok(state) => println!("Pin is {:?}", state),
+//!   Err(e) => {
+//!     match *e.kind() {
+//!       ErrorKind::NotExported(..) => println!("Export the pin first!"),
+//!       ErrorKind::PermissionDenied(..) => println!("Try running as root."),
+//!       _ => println!("Something else went wrong: {}", e),
+//!     }
+//!   }
+//! }
+//! ```
 
-error_chain!{}
+use nix;
+use std::io;
+use std::path::{Path, PathBuf};
+
+error_chain! {
+  errors {
+    /// The device isn't exported or enabled, so its file doesn't exist.
+    NotExported(device: String, path: PathBuf) {
+      description("device not exported")
+      display("{} isn't exported or enabled ({})", device, path.display())
+    }
+    /// The process lacks the permissions needed to access the device.
+    PermissionDenied(device: String, path: PathBuf) {
+      description("permission denied")
+      display("Permission denied accessing {} ({})", device, path.display())
+    }
+    /// The device is in use, e.g. by a kernel driver or because the pin is
+    /// muxed to another function.
+    Busy(device: String, path: PathBuf) {
+      description("device busy")
+      display("{} is busy ({})", device, path.display())
+    }
+    /// The pin doesn't exist or can't be used for the requested function.
+    InvalidPin(pin: String) {
+      description("invalid pin")
+      display("Invalid pin: {}", pin)
+    }
+    /// A value was out of range, or the kernel rejected or returned a
+    /// malformed value.
+    InvalidValue(device: String, value: String) {
+      description("invalid value")
+      display("Invalid value for {}: {}", device, value)
+    }
+    /// The I2C slave didn't acknowledge its address or the data sent to it.
+    NoAcknowledge(device: String) {
+      description("no acknowledge")
+      display("{} got no acknowledge from the slave", device)
+    }
+    /// The device didn't respond in time.
+    Timeout(device: String) {
+      description("timed out")
+      display("Timed out waiting for {}", device)
+    }
+    /// The device produced data faster than it was read, so some was lost.
+    Overrun(device: String) {
+      description("overrun")
+      display("{} overran, samples were lost", device)
+    }
+    /// Any other I/O error while accessing the device.
+    Io(device: String, path: PathBuf) {
+      description("I/O error")
+      display("I/O error accessing {} ({})", device, path.display())
+    }
+  }
+}
+
+impl Error {
+  /// Wraps an I/O error that occurred while accessing a device file,
+  /// picking the error kind that matches its cause.
+  pub fn from_io(err: io::Error, device: &str, path: &Path) -> Error {
+    let device = device.to_string();
+    let path = path.to_path_buf();
+    let kind = match err.kind() {
+      io::ErrorKind::NotFound => ErrorKind::NotExported(device, path),
+      io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied(device, path),
+      io::ErrorKind::TimedOut => ErrorKind::Timeout(device),
+      _ => {
+        match err.raw_os_error().map(nix::Errno::from_i32) {
+          Some(nix::Errno::EBUSY) => ErrorKind::Busy(device, path),
+          Some(nix::Errno::ENODEV) |
+          Some(nix::Errno::ENXIO) => ErrorKind::NotExported(device, path),
+          Some(nix::Errno::ETIMEDOUT) => ErrorKind::Timeout(device),
+          _ => ErrorKind::Io(device, path),
+        }
+      }
+    };
+    Error::with_chain(err, kind)
+  }
+
+  /// Wraps an error returned by a system call (such as an ioctl) on a device
+  /// file, picking the error kind that matches its cause.
+  pub fn from_nix(err: nix::Error, device: &str, path: &Path) -> Error {
+    Error::from_io(io::Error::from(err), device, path)
+  }
+}
@@ -15,22 +15,42 @@
 //! for P9.22.
 //! A convenient list of pin identifiers can be found through an online search
 //! of "BeagleBone pinout".
+//! Alternatively, enable `Board::set_auto_pinmux()` and `GPIO::new()` will
+//! configure the pin itself.
 //!
 //! Note: not all pins are available to be set as GPIOs with the default device
 //! tree overlay, as they are used for other interfaces such as HDMI.
 //! You may need to change the overlay from the default to access these blocked
 //! pins.
+//!
+//! Pins are driven through the sysfs interface (`/sys/class/gpio`) by default.
+//! Kernels that no longer provide it can use the GPIO character devices
+//! (`/dev/gpiochipN`) instead by selecting `GPIOBackend::CharDev` on the
+//! `Board`; the `GPIO` API is the same for both.
+//!
+//! A `GPIO` tracks its direction in its type: `GPIO::new()` returns a
+//! `GPIO<Unexported>`, which `into_input()` and `into_output()` turn into a
+//! `GPIO<Input>` or `GPIO<Output>`.
+//! Only outputs can be written to, and only inputs can wait for interrupts.
 
-use enums::DeviceState;
+use board::Board;
+use enums::SafeState;
 use errors::*;
+use gpiochip::{LINES_MAX, LineConfig, LineEvent, LineRequest};
+use nix::poll::{POLLERR, POLLPRI};
+use pinmux::{self, PinMode};
 use pins::Pin;
 use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::Duration;
 use util::*;
 
 /// The direction of the pin, which can be either an input or output.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum PinDirection {
   /// GPIO in
   In,
@@ -39,7 +59,7 @@ pub enum PinDirection {
 }
 
 /// The logic level of an output GPIO pin, either high or low.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum PinState {
   /// GPIO logic high
   High,
@@ -47,18 +67,80 @@ pub enum PinState {
   Low,
 }
 
+/// The signal edge(s) on which an input GPIO pin generates an interrupt.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Edge {
+  /// Interrupts disabled
+  None,
+  /// Interrupt on a low to high transition
+  Rising,
+  /// Interrupt on a high to low transition
+  Falling,
+  /// Interrupt on any transition
+  Both,
+}
+
+/// The kernel interface used to drive GPIO pins.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GPIOBackend {
+  /// The sysfs interface under `/sys/class/gpio`
+  Sysfs,
+  /// The character devices `/dev/gpiochipN`
+  CharDev,
+}
+
+/// The state of a GPIO pin that isn't exported.
+#[derive(Debug)]
+pub struct Unexported;
+
+/// The state of a GPIO pin that is exported and configured as an input.
+#[derive(Debug)]
+pub struct Input;
+
+/// The state of a GPIO pin that is exported and configured as an output.
+#[derive(Debug)]
+pub struct Output;
+
 /// Represents a pin configured as a GPIO.
+///
+/// The type parameter tracks whether the pin is unexported, an input or an
+/// output, so e.g. writing to an input is a compile error.
+/// The `into_*()` methods consume the pin and return it in its new state.
+///
+/// When dropped, a pin this handle exported is put in its safe state (see
+/// `set_safe_state()`).
 #[derive(Debug)]
-pub struct GPIO {
+pub struct GPIO<S = Unexported> {
   pin_num: u8,
-  pin_path: PathBuf,
+  backend: Backend,
+  direction: Option<PinDirection>,
+  exported_by_us: bool,
+  safe_state: SafeState,
+  state: PhantomData<S>,
+}
+
+/// The backend-specific state of a GPIO pin.
+#[derive(Debug)]
+enum Backend {
+  Sysfs {
+    board: Board,
+    pin_path: PathBuf,
+    value_file: Option<File>,
+  },
+  CharDev {
+    chip_path: PathBuf,
+    offset: u32,
+    config: LineConfig,
+    line: Option<LineRequest>,
+  },
 }
 
-impl GPIO {
+impl GPIO<Unexported> {
   /// Creates a new GPIO pin object.
   ///
   /// Note: this doesn't do any sort of initialization, you have to call
-  /// `set_direction()`, `set_export()`, yourself.
+  /// `into_input()` or `into_output()` to export the pin and set its
+  /// direction.
   ///
   /// Furthermore, you will need to configure the selected pin as a GPIO
   /// prior to use using the `config-pin` utility.
@@ -70,119 +152,331 @@ impl GPIO {
   /// ```
   /// use libbeaglebone::prelude::*;
   ///
-  /// // Create a new GPIO object using pin #45
-  /// let pin = GPIO::new(GPIO_P8_11);
+  /// // Create a new GPIO object using P8.11 (GPIO #45)
+  /// let pin = GPIO::new(&Board::new(), P8_11).unwrap();
   /// ```
   ///
   /// # Errors
   ///
-  /// Fails if the `pin_num` is invalid, i.e. a nonexistent pin.
-  pub fn new(pin: Pin) -> GPIO {
-    GPIO {
-      pin_num: pin as u8,
-      pin_path: PathBuf::from(format!("/sys/class/gpio/gpio{}", pin as u8)),
+  /// Fails if the pin can't be used as a GPIO, e.g. a power pin.
+  /// Fails if the board muxes pins and the pin can't be muxed as a GPIO.
+  pub fn new(board: &Board, pin: Pin) -> Result<GPIO<Unexported>> {
+    let pin_num = gpio_num(pin)?;
+    if board.auto_pinmux() {
+      pinmux::set_mode(board, pin, PinMode::Gpio)?;
     }
+    Ok(GPIO {
+      pin_num: pin_num,
+      backend: match board.gpio_backend() {
+        GPIOBackend::Sysfs => Backend::Sysfs {
+          board: board.clone(),
+          pin_path: board.sysfs_path(format!("class/gpio/gpio{}", pin_num)),
+          value_file: None,
+        },
+        GPIOBackend::CharDev => Backend::CharDev {
+          chip_path: board.devfs_path(format!("gpiochip{}", pin_num / 32)),
+          offset: (pin_num % 32) as u32,
+          config: LineConfig::default(),
+          line: None,
+        },
+      },
+      direction: None,
+      exported_by_us: false,
+      safe_state: SafeState::DriveLow,
+      state: PhantomData,
+    })
   }
 
-  /// Sets the direction of the pin as either an input or output.
+  /// Exports the pin and makes it an input.
+  ///
+  /// Pins that are already exported (e.g. by another process) are used as
+  /// they are.
   ///
   /// # Examples
   ///
   /// ```no_run
   /// use libbeaglebone::prelude::*;
   ///
-  /// let pin = GPIO::new(GPIO_P8_11);
+  /// let pin = GPIO::new(&Board::new(), P8_11).unwrap();
   ///
-  /// // Make the pin an output
-  /// pin.set_direction(PinDirection::Out).unwrap();
-  ///
-  /// // Make the in an input
-  /// pin.set_direction(PinDirection::In).unwrap();
+  /// // Export the pin and make it an input
+  /// let pin = pin.into_input().unwrap();
   /// ```
   ///
   /// # Errors
   ///
-  /// Fails if the GPIO pin is not configured correctly.
+  /// Fails to export the pin if it isn't configured correctly.
   /// Check the module documentation to see how to configure the pin correctly.
-  pub fn set_direction(&self, direction: PinDirection) -> Result<()> {
-    // Write "in" or "out" to the sysfs device file depending on PinDirection
-    let path = format!("/sys/class/gpio/gpio{}/direction", &self.pin_num);
-    path.write_file(match direction {
-      PinDirection::In => "in",
-      PinDirection::Out => "out",
-    })
-        .chain_err(|| {
-      format!("Failed to set GPIO pin #{} direction", &self.pin_num)
-    })?;
-    Ok(())
+  pub fn into_input(mut self) -> Result<GPIO<Input>> {
+    self.export(PinDirection::In, PinState::Low)?;
+    self.set_direction(PinDirection::In, PinState::Low)?;
+    Ok(self.into_state())
   }
 
-  /// Exports or unexports a GPIO pin.
+  /// Exports the pin and makes it an output, driving `initial_state`.
   ///
-  /// True corresponds to export, false corresponds to unexport.
-  /// `set_export()` won't try to export pins that are already exported, or
-  /// unexport pins that aren't exported.
+  /// The pin switches straight to the initial state, without glitching to
+  /// another level first.
   ///
   /// # Examples
   ///
   /// ```no_run
   /// use libbeaglebone::prelude::*;
   ///
-  /// let mut pin = GPIO::new(GPIO_P8_11);
+  /// let pin = GPIO::new(&Board::new(), P8_11).unwrap();
   ///
-  /// // Try to export the pin
-  /// pin.set_export(DeviceState::Exported).unwrap();
-  ///
-  /// // Try to unexport the pin
-  /// pin.set_export(DeviceState::Unexported).unwrap();
+  /// // Export the pin and make it an output that starts off low
+  /// let mut pin = pin.into_output(PinState::Low).unwrap();
   /// ```
   ///
   /// # Errors
   ///
   /// Fails to export the pin if it isn't configured correctly.
   /// Check the module documentation to see how to configure the pin correctly.
-  pub fn set_export(&self, state: DeviceState) -> Result<()> {
-    // Note: if the pin path exists, the pin is already exported.
-    // If the pin path doesn't exist, the pin isn't exported.
-    // Exporting/unexporting is done by writing the pin number to the
-    // export/unexport file.
-
-    // The pin path doesn't exist and we want to export, try to write to the file
-    if state == DeviceState::Exported && !self.pin_path.exists() {
-      File::create("/sys/class/gpio/export")
-        .chain_err(|| "Failed to open GPIO export file")?
-        .write_all(self.pin_num.to_string().as_bytes())
-        .chain_err(|| format!("Failed to export GPIO pin #{}", &self.pin_num))?;
-
-    }
-    // Try to unexport if the path exists, otherwise the pin is unexported and there's nothing to do
-    else if state == DeviceState::Unexported && self.pin_path.exists() {
-      File::create("/sys/class/gpio/unexport")
-        .chain_err(|| "Failed to open GPIO unexport file")?
-        .write_all(self.pin_num.to_string().as_bytes())
-        .chain_err(|| format!("Failed to unexport GPIO pin #{}", &self.pin_num))?;
+  pub fn into_output(mut self, initial_state: PinState) -> Result<GPIO<Output>> {
+    self.export(PinDirection::Out, initial_state)?;
+    self.set_direction(PinDirection::Out, initial_state)?;
+    Ok(self.into_state())
+  }
+}
+
+impl GPIO<Input> {
+  /// Makes the pin an output, driving `initial_state`.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel refuses to change the pin's direction.
+  pub fn into_output(mut self, initial_state: PinState) -> Result<GPIO<Output>> {
+    self.set_direction(PinDirection::Out, initial_state)?;
+    Ok(self.into_state())
+  }
+
+  /// Unexports the pin.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel refuses to unexport the pin.
+  pub fn into_unexported(mut self) -> Result<GPIO<Unexported>> {
+    self.unexport()?;
+    Ok(self.into_state())
+  }
+
+  /// Reads the logic level of the pin, returning either high or low.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let pin = GPIO::new(&Board::new(), P8_11).unwrap().into_input().unwrap();
+  ///
+  /// // Read the pin's state
+  /// if pin.read().unwrap() == PinState::High {
+  ///   println!("Pin is high!");
+  /// }
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails to read from the pin if the pin isn't configured correctly.
+  /// Check the module documentation to see how to configure the pin correctly.
+  pub fn read(&self) -> Result<(PinState)> {
+    self.read_level()
+  }
+
+  /// Sets the signal edge(s) on which the pin generates an interrupt.
+  ///
+  /// Use `wait_for_edge()` to block until an interrupt occurs.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut pin = GPIO::new(&Board::new(), P8_11).unwrap().into_input().unwrap();
+  ///
+  /// // Generate an interrupt whenever the pin goes high
+  /// pin.set_edge(Edge::Rising).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if the pin doesn't support interrupts.
+  pub fn set_edge(&mut self, edge: Edge) -> Result<()> {
+    match self.backend {
+      Backend::Sysfs { ref pin_path, .. } => {
+        pin_path.join("edge").write_file(&gpio_name(self.pin_num),
+                                         match edge {
+                                           Edge::None => "none",
+                                           Edge::Rising => "rising",
+                                           Edge::Falling => "falling",
+                                           Edge::Both => "both",
+                                         })
+      }
+      Backend::CharDev { ref mut config, ref line, .. } => {
+        config.edge = edge;
+        match *line {
+          Some(ref line) => line.set_config(config),
+          None => Ok(()),
+        }
+      }
     }
-    Ok(())
   }
 
-  /// Writes to the pin, setting it either logic high or low.
+  /// Blocks until the pin generates an interrupt, then returns its new logic
+  /// level.
+  ///
+  /// The thread sleeps in `poll(2)` while waiting, so no CPU time is used.
+  /// Pass `None` as the timeout to wait forever.
+  /// Edges that occur between calls are not lost: the next call returns
+  /// immediately.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  /// use std::time::Duration;
+  ///
+  /// let mut pin = GPIO::new(&Board::new(), P8_11).unwrap().into_input().unwrap();
+  ///
+  /// // Enable interrupts
+  /// pin.set_edge(Edge::Both).unwrap();
+  ///
+  /// // Wait up to a second for the pin to change
+  /// match pin.wait_for_edge(Some(Duration::from_secs(1))) {
+  ///   Ok(state) => println!("Pin is now {:?}", state),
+  ///   Err(_) => println!("Nothing happened"),
+  /// }
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if the timeout elapses before an interrupt occurs.
+  /// Fails with `InvalidValue` if no edge is set, as the wait would never
+  /// end.
+  pub fn wait_for_edge(&mut self, timeout: Option<Duration>) -> Result<PinState> {
+    let pin_num = self.pin_num;
+    match self.backend {
+      Backend::Sysfs { ref pin_path, ref mut value_file, .. } => {
+        let edge = pin_path.join("edge").read_file(&gpio_name(pin_num))?;
+        if edge.trim() == "none" {
+          bail!(ErrorKind::InvalidValue(gpio_name(pin_num), "edge none".to_string()));
+        }
+        if value_file.is_none() {
+          // Reading the value file clears any stale interrupt, so the first
+          // poll only wakes up on a new edge.
+          let path = pin_path.join("value");
+          let mut file = File::open(&path)
+            .map_err(|e| Error::from_io(e, &gpio_name(pin_num), &path))?;
+          let _ = read_value(pin_num, &path, &mut file)?;
+          *value_file = Some(file);
+        }
+        let path = pin_path.join("value");
+        let file = value_file.as_mut().unwrap();
+
+        if !poll_file(file, POLLPRI | POLLERR, timeout)
+              .map_err(|e| Error::from_nix(e, &gpio_name(pin_num), &path))? {
+          bail!(ErrorKind::Timeout(gpio_name(pin_num)));
+        }
+        read_value(pin_num, &path, file)
+      }
+      Backend::CharDev { ref chip_path, ref config, ref line, .. } => {
+        if config.edge == Edge::None {
+          bail!(ErrorKind::InvalidValue(gpio_name(pin_num), "edge none".to_string()));
+        }
+        let event = requested_line(pin_num, chip_path, line)?.wait_event(timeout)?;
+        Ok(if event.edge == Edge::Rising {
+             PinState::High
+           } else {
+             PinState::Low
+           })
+      }
+    }
+  }
+
+  /// Blocks until the pin generates an interrupt and returns the event,
+  /// including the time at which the kernel saw the edge.
+  ///
+  /// Pass `None` as the timeout to wait forever.
+  /// This requires the `GPIOBackend::CharDev` backend, as the sysfs interface
+  /// doesn't report event timestamps.
   ///
   /// # Examples
   ///
   /// ```no_run
   /// use libbeaglebone::prelude::*;
   ///
-  /// let mut pin = GPIO::new(GPIO_P8_11);
+  /// let mut board = Board::new();
+  /// board.set_gpio_backend(GPIOBackend::CharDev);
+  /// let mut pin = GPIO::new(&board, P8_11).unwrap().into_input().unwrap();
+  ///
+  /// // Enable interrupts
+  /// pin.set_edge(Edge::Both).unwrap();
   ///
-  /// // Try to export the pin and make it an output
-  /// pin.set_export(DeviceState::Exported).unwrap();
-  /// pin.set_direction(PinDirection::Out).unwrap();
+  /// // Print the time of every edge
+  /// loop {
+  ///   let event = pin.wait_for_event(None).unwrap();
+  ///   println!("{:?} edge at {:?}", event.edge, event.timestamp);
+  /// }
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the pin uses the sysfs backend, or if no
+  /// edge is set.
+  /// Fails if the timeout elapses before an interrupt occurs.
+  pub fn wait_for_event(&mut self, timeout: Option<Duration>) -> Result<LineEvent> {
+    match self.backend {
+      Backend::Sysfs { .. } => {
+        // The sysfs backend doesn't report edge events
+        bail!(ErrorKind::InvalidValue(gpio_name(self.pin_num), "sysfs backend".to_string()))
+      }
+      Backend::CharDev { ref chip_path, ref config, ref line, .. } => {
+        if config.edge == Edge::None {
+          bail!(ErrorKind::InvalidValue(gpio_name(self.pin_num), "edge none".to_string()));
+        }
+        requested_line(self.pin_num, chip_path, line)?.wait_event(timeout)
+      }
+    }
+  }
+}
+
+impl GPIO<Output> {
+  /// Makes the pin an input.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel refuses to change the pin's direction.
+  pub fn into_input(mut self) -> Result<GPIO<Input>> {
+    self.set_direction(PinDirection::In, PinState::Low)?;
+    Ok(self.into_state())
+  }
+
+  /// Unexports the pin.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel refuses to unexport the pin.
+  pub fn into_unexported(mut self) -> Result<GPIO<Unexported>> {
+    self.unexport()?;
+    Ok(self.into_state())
+  }
+
+  /// Writes to the pin, setting it either logic high or low.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let pin = GPIO::new(&Board::new(), P8_11).unwrap();
+  /// let mut pin = pin.into_output(PinState::Low).unwrap();
   ///
   /// // Set the pin to logic high
   /// pin.write(PinState::High).unwrap();
   ///
   /// // Set the pin to logic low
-  /// pin.write(PinState::High).unwrap();
+  /// pin.write(PinState::Low).unwrap();
   /// ```
   ///
   /// # Errors
@@ -190,52 +484,452 @@ impl GPIO {
   /// Fails to write to the pin if the pin isn't configured correctly.
   /// Check the module documentation to see how to configure the pin correctly.
   pub fn write(&mut self, state: PinState) -> Result<()> {
-    let path = format!("/sys/class/gpio/gpio{}/value", &self.pin_num);
-    // Write a "0" or "1" to the pin's "value" device file depending on PinState
-    path.write_file(match state {
-      PinState::High => "1",
-      PinState::Low => "0",
-    })
-        .chain_err(|| {
-      format!(
-        "Failed to set GPIO pin #{} state to {:?}",
-        &self.pin_num,
-        state
-      )
-    })?;
+    let pin_num = self.pin_num;
+    match self.backend {
+      Backend::Sysfs { ref pin_path, .. } => {
+        // Write a "0" or "1" to the pin's "value" device file depending on PinState
+        pin_path.join("value").write_file(&gpio_name(pin_num),
+                                          match state {
+                                            PinState::High => "1",
+                                            PinState::Low => "0",
+                                          })
+      }
+      Backend::CharDev { ref chip_path, ref mut config, ref line, .. } => {
+        // Remember the level so it's restored if the line is requested again
+        config.output_values = if state == PinState::High { 1 } else { 0 };
+        requested_line(pin_num, chip_path, line)?.set_values(config.output_values, 1)
+      }
+    }
+  }
+
+  /// Reads the level the pin is being driven to.
+  ///
+  /// # Errors
+  ///
+  /// Fails to read from the pin if the pin isn't configured correctly.
+  pub fn read(&self) -> Result<PinState> {
+    self.read_level()
+  }
+}
+
+impl<S> GPIO<S> {
+  /// Returns what happens to the pin when the handle is dropped.
+  pub fn safe_state(&self) -> SafeState {
+    self.safe_state
+  }
+
+  /// Sets what happens to the pin when the handle is dropped, including
+  /// when a panic unwinds past it.
+  ///
+  /// The default is `SafeState::DriveLow`, which drives outputs low and then
+  /// unexports the pin.
+  /// `SafeState::Unexport` unexports the pin as it is, `SafeState::Disable`
+  /// turns outputs into inputs and `SafeState::Leave` leaves the pin
+  /// configured.
+  /// Pins that were never exported through this handle are left alone.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let pin = GPIO::new(&Board::new(), P8_11).unwrap();
+  /// let mut pin = pin.into_output(PinState::High).unwrap();
+  ///
+  /// // Keep driving the pin high after the program exits
+  /// pin.set_safe_state(SafeState::Leave);
+  /// ```
+  pub fn set_safe_state(&mut self, safe_state: SafeState) {
+    self.safe_state = safe_state;
+  }
+
+  /// Moves the pin into another state.
+  fn into_state<T>(self) -> GPIO<T> {
+    // The fields are moved out without dropping `self`, which would reset
+    // the pin.
+    let gpio = GPIO {
+      pin_num: self.pin_num,
+      backend: unsafe { ptr::read(&self.backend) },
+      direction: self.direction,
+      exported_by_us: self.exported_by_us,
+      safe_state: self.safe_state,
+      state: PhantomData,
+    };
+    mem::forget(self);
+    gpio
+  }
+
+  /// Puts the pin in its safe state, if this handle exported it.
+  fn reset(&mut self) -> Result<()> {
+    let direction = match self.direction {
+      Some(direction) if self.exported_by_us => direction,
+      _ => return Ok(()),
+    };
+    match self.safe_state {
+      SafeState::Leave => Ok(()),
+      SafeState::Unexport => self.unexport(),
+      SafeState::DriveLow => {
+        if direction == PinDirection::Out {
+          self.set_direction(PinDirection::Out, PinState::Low)?;
+        }
+        self.unexport()
+      }
+      SafeState::Disable => {
+        if direction == PinDirection::Out {
+          self.set_direction(PinDirection::In, PinState::Low)?;
+        }
+        Ok(())
+      }
+    }
+  }
+
+  /// Exports the pin, if it isn't already.
+  ///
+  /// `direction` and `initial_state` are only used by the character device
+  /// backend, which sets them when it requests the line.
+  fn export(&mut self, direction: PinDirection, initial_state: PinState) -> Result<()> {
+    let pin_num = self.pin_num;
+    match self.backend {
+      Backend::Sysfs { ref board, ref pin_path, .. } => {
+        // Note: if the pin path exists, the pin is already exported.
+        // Exporting is done by writing the pin number to the export file.
+        if !pin_path.exists() {
+          board.sysfs_path("class/gpio/export")
+               .write_file(&gpio_name(pin_num), &pin_num.to_string())?;
+          self.exported_by_us = true;
+        }
+      }
+      Backend::CharDev { ref chip_path, offset, ref mut config, ref mut line } => {
+        // The character devices have no notion of exporting: a pin is
+        // "exported" while we hold a request for its line.
+        if line.is_none() {
+          config.direction = direction;
+          config.output_values = if initial_state == PinState::High { 1 } else { 0 };
+          *line = Some(LineRequest::new(chip_path, &[offset], config)?);
+          self.exported_by_us = true;
+        }
+      }
+    }
     Ok(())
   }
 
-  /// Reads the logic level of the pin, returning either high or low.
+  /// Unexports the pin, if it's exported.
+  fn unexport(&mut self) -> Result<()> {
+    let pin_num = self.pin_num;
+    match self.backend {
+      Backend::Sysfs { ref board, ref pin_path, ref mut value_file } => {
+        if pin_path.exists() {
+          *value_file = None;
+          board.sysfs_path("class/gpio/unexport")
+               .write_file(&gpio_name(pin_num), &pin_num.to_string())?;
+        }
+      }
+      Backend::CharDev { ref mut line, .. } => *line = None,
+    }
+    self.direction = None;
+    self.exported_by_us = false;
+    Ok(())
+  }
+
+  /// Sets the direction of the exported pin, driving `initial_state` if it
+  /// becomes an output.
+  fn set_direction(&mut self, direction: PinDirection, initial_state: PinState) -> Result<()> {
+    let pin_num = self.pin_num;
+    match self.backend {
+      Backend::Sysfs { ref pin_path, ref mut value_file, .. } => {
+        // Writing "high" or "low" makes the pin an output with that level
+        *value_file = None;
+        pin_path.join("direction")
+                .write_file(&gpio_name(pin_num),
+                            match (direction, initial_state) {
+                              (PinDirection::In, _) => "in",
+                              (PinDirection::Out, PinState::High) => "high",
+                              (PinDirection::Out, PinState::Low) => "low",
+                            })?;
+      }
+      Backend::CharDev { ref mut config, ref line, .. } => {
+        config.direction = direction;
+        config.output_values = if initial_state == PinState::High { 1 } else { 0 };
+        if let Some(ref line) = *line {
+          line.set_config(config)?;
+        }
+      }
+    }
+    self.direction = Some(direction);
+    Ok(())
+  }
+
+  /// Reads the logic level of the exported pin.
+  fn read_level(&self) -> Result<PinState> {
+    match self.backend {
+      Backend::Sysfs { ref pin_path, .. } => {
+        // Read from the file and match the resulting bool to a PinState
+        match parse_file::<u8>(&pin_path.join("value"), &gpio_name(self.pin_num))? {
+          1 => Ok(PinState::High),
+          0 => Ok(PinState::Low),
+          value => bail!(ErrorKind::InvalidValue(gpio_name(self.pin_num), value.to_string())),
+        }
+      }
+      Backend::CharDev { ref chip_path, ref line, .. } => {
+        match requested_line(self.pin_num, chip_path, line)?.get_values(1)? {
+          0 => Ok(PinState::Low),
+          _ => Ok(PinState::High),
+        }
+      }
+    }
+  }
+}
+
+impl<S> Drop for GPIO<S> {
+  fn drop(&mut self) {
+    // Pins this handle didn't export are left as they were
+    let _ = self.reset();
+  }
+}
+
+/// Represents a group of GPIO pins that are read and written together.
+///
+/// Bit `i` of every value refers to the `i`th pin the group was created with.
+/// The group always uses the GPIO character devices: pins that share a GPIO
+/// bank are held in a single line request, so they're updated atomically.
+/// Pins in different banks are updated one bank after another.
+#[derive(Debug)]
+pub struct GPIOGroup {
+  num_pins: usize,
+  direction: PinDirection,
+  banks: Vec<GroupBank>,
+}
+
+/// The pins of a group that belong to one GPIO bank.
+#[derive(Debug)]
+struct GroupBank {
+  line: LineRequest,
+  // The group bit of each line in the request, in request order
+  bits: Vec<usize>,
+}
+
+impl GPIOGroup {
+  /// Creates a new group of GPIO pins and requests their lines.
+  ///
+  /// Outputs start out driven low.
   ///
   /// # Examples
   ///
   /// ```no_run
   /// use libbeaglebone::prelude::*;
   ///
-  /// let mut pin = GPIO::new(GPIO_P8_11);
+  /// // Create an 8-bit output bus, with P8.11 as the least significant bit.
+  /// let board = Board::new();
+  /// let pins = [P8_11, P8_12, P8_15, P8_16,
+  ///             P8_17, P8_18, P8_26, P9_12];
+  /// let mut bus = GPIOGroup::new(&board, &pins, PinDirection::Out).unwrap();
   ///
-  /// // Try to export the pin and make it an input
-  /// pin.set_export(DeviceState::Exported).unwrap();
-  /// pin.set_direction(PinDirection::In).unwrap();
+  /// // Put 0xA5 on the bus.
+  /// bus.write(0xA5).unwrap();
+  /// ```
   ///
-  /// // Read the pin's state
-  /// if pin.read().unwrap() == PinState::High {
-  ///   println!("Pin is high!");
+  /// # Errors
+  ///
+  /// Fails if no pins or more than 64 pins are given.
+  /// Fails if a GPIO chip can't be opened or the kernel refuses to hand over
+  /// the lines, e.g. because a pin is in use.
+  pub fn new(board: &Board, pins: &[Pin], direction: PinDirection) -> Result<GPIOGroup> {
+    if pins.is_empty() || pins.len() > LINES_MAX {
+      bail!(ErrorKind::InvalidValue(
+        "GPIO group".to_string(),
+        format!("{} pins", pins.len())
+      ));
+    }
+
+    // Sort the pins into their banks, keeping track of each pin's group bit
+    let mut bank_pins: Vec<(u8, Vec<u32>, Vec<usize>)> = Vec::new();
+    for (bit, &pin) in pins.iter().enumerate() {
+      let pin_num = gpio_num(pin)?;
+      if board.auto_pinmux() {
+        pinmux::set_mode(board, pin, PinMode::Gpio)?;
+      }
+      let chip = pin_num / 32;
+      match bank_pins.iter().position(|&(c, _, _)| c == chip) {
+        Some(i) => {
+          bank_pins[i].1.push((pin_num % 32) as u32);
+          bank_pins[i].2.push(bit);
+        }
+        None => bank_pins.push((chip, vec![(pin_num % 32) as u32], vec![bit])),
+      }
+    }
+
+    let config = LineConfig {
+      direction: direction,
+      ..Default::default()
+    };
+    let mut banks = Vec::with_capacity(bank_pins.len());
+    for (chip, offsets, bits) in bank_pins {
+      banks.push(GroupBank {
+        line: LineRequest::new(&board.devfs_path(format!("gpiochip{}", chip)), &offsets, &config)?,
+        bits: bits,
+      });
+    }
+
+    Ok(GPIOGroup {
+      num_pins: pins.len(),
+      direction: direction,
+      banks: banks,
+    })
+  }
+
+  /// Returns the number of pins in the group.
+  pub fn num_pins(&self) -> usize {
+    self.num_pins
+  }
+
+  /// Sets the direction of every pin in the group.
+  ///
+  /// Pins switched to outputs keep the value last written to them, or low if
+  /// they were never written.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel refuses to reconfigure the lines.
+  pub fn set_direction(&mut self, direction: PinDirection) -> Result<()> {
+    for bank in &self.banks {
+      let output_values = if direction == PinDirection::Out && self.direction == PinDirection::Out {
+        bank.line.get_values(!0)?
+      } else {
+        0
+      };
+      bank.line
+          .set_config(&LineConfig {
+            direction: direction,
+            output_values: output_values,
+            ..Default::default()
+          })?;
+    }
+    self.direction = direction;
+    Ok(())
+  }
+
+  /// Reads the logic level of every pin in the group as a bitmask.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let board = Board::new();
+  /// let inputs = GPIOGroup::new(&board, &[P8_7, P8_8], PinDirection::In).unwrap();
+  ///
+  /// if inputs.read().unwrap() == 0b11 {
+  ///   println!("Both pins are high!");
   /// }
   /// ```
   ///
   /// # Errors
   ///
-  /// Fails to read from the pin if the pin isn't configured correctly.
-  /// Check the module documentation to see how to configure the pin correctly.
-  pub fn read(&self) -> Result<(PinState)> {
-    let path = format!("/sys/class/gpio/gpio{}/value", &self.pin_num);
-    // Read from the file and match the resulting bool to a PinState
-    match path.read_file().unwrap().trim() {
-      "1" => Ok(PinState::High),
-      "0" => Ok(PinState::Low),
-      _ => bail!(format!("Invalid value read from file {}", &path)),
+  /// Fails if the kernel is unable to read the lines.
+  pub fn read(&self) -> Result<u64> {
+    let mut value = 0;
+    for bank in &self.banks {
+      let line_values = bank.line.get_values(!0)?;
+      for (line, &bit) in bank.bits.iter().enumerate() {
+        if line_values & (1 << line) != 0 {
+          value |= 1 << bit;
+        }
+      }
+    }
+    Ok(value)
+  }
+
+  /// Writes a bitmask to the group, setting every pin at once.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the pins aren't outputs or if the kernel is unable to write to
+  /// the lines.
+  pub fn write(&mut self, value: u64) -> Result<()> {
+    for bank in &self.banks {
+      let mut line_values = 0;
+      for (line, &bit) in bank.bits.iter().enumerate() {
+        if value & (1 << bit) != 0 {
+          line_values |= 1 << line;
+        }
+      }
+      bank.line.set_values(line_values, !0)?;
     }
+    Ok(())
+  }
+}
+
+#[cfg(feature = "hal")]
+impl<S> ::embedded_hal::digital::ErrorType for GPIO<S> {
+  type Error = Error;
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::digital::OutputPin for GPIO<Output> {
+  fn set_low(&mut self) -> Result<()> {
+    self.write(PinState::Low)
+  }
+
+  fn set_high(&mut self) -> Result<()> {
+    self.write(PinState::High)
+  }
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::digital::InputPin for GPIO<Input> {
+  fn is_high(&mut self) -> Result<bool> {
+    Ok(self.read()? == PinState::High)
+  }
+
+  fn is_low(&mut self) -> Result<bool> {
+    Ok(self.read()? == PinState::Low)
+  }
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::digital::StatefulOutputPin for GPIO<Output> {
+  // Reading an output pin returns the level it's being driven to
+  fn is_set_high(&mut self) -> Result<bool> {
+    Ok(self.read()? == PinState::High)
+  }
+
+  fn is_set_low(&mut self) -> Result<bool> {
+    Ok(self.read()? == PinState::Low)
+  }
+}
+
+/// Returns the GPIO number of a pin, failing if it isn't a GPIO.
+fn gpio_num(pin: Pin) -> Result<u8> {
+  match pin.gpio_num() {
+    Some(pin_num) => Ok(pin_num),
+    None => bail!(ErrorKind::InvalidPin(pin.to_string())),
+  }
+}
+
+fn gpio_name(pin_num: u8) -> String {
+  format!("GPIO #{}", pin_num)
+}
+
+/// Returns the line request of a pin using the character device backend.
+fn requested_line<'a>(pin_num: u8,
+                      chip_path: &Path,
+                      line: &'a Option<LineRequest>)
+                      -> Result<&'a LineRequest> {
+  match *line {
+    Some(ref line) => Ok(line),
+    None => bail!(ErrorKind::NotExported(gpio_name(pin_num), chip_path.to_path_buf())),
+  }
+}
+
+/// Reads the logic level of a pin from its already opened value file.
+fn read_value(pin_num: u8, path: &Path, file: &mut File) -> Result<PinState> {
+  let mut value_str = String::new();
+  let _ = file.seek(SeekFrom::Start(0))
+              .and_then(|_| file.read_to_string(&mut value_str))
+              .map_err(|e| Error::from_io(e, &gpio_name(pin_num), path))?;
+  match value_str.trim() {
+    "1" => Ok(PinState::High),
+    "0" => Ok(PinState::Low),
+    value => bail!(ErrorKind::InvalidValue(gpio_name(pin_num), value.to_string())),
   }
 }
@@ -0,0 +1,381 @@
+//! The GPIO character device module.
+//!
+//! Newer kernels deprecate the sysfs GPIO interface in favour of the
+//! `/dev/gpiochipN` character devices.
+//! This module wraps the version 2 line ioctls of that interface: requesting
+//! lines, reading and writing their values, reconfiguring them and reading
+//! edge events with kernel timestamps.
+//!
+//! Most users won't need this module directly: select
+//! `GPIOBackend::CharDev` on the `Board` and the `GPIO` API will use it.
+//!
+//! The AM335x has four GPIO banks of 32 lines each, exposed as `gpiochip0`
+//! through `gpiochip3`, so GPIO number `n` is line `n % 32` of chip
+//! `n / 32`.
+
+use errors::*;
+use gpio::{Edge, PinDirection};
+use nix::poll::POLLIN;
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::Duration;
+use util::*;
+
+/// The maximum number of lines in a single request.
+pub const LINES_MAX: usize = 64;
+
+// Constants extracted from linux/gpio.h
+const GPIO_V2_LINE_NUM_ATTRS_MAX: usize = 10;
+
+const GPIO_V2_LINE_FLAG_INPUT: u64 = 1 << 2;
+const GPIO_V2_LINE_FLAG_OUTPUT: u64 = 1 << 3;
+const GPIO_V2_LINE_FLAG_EDGE_RISING: u64 = 1 << 4;
+const GPIO_V2_LINE_FLAG_EDGE_FALLING: u64 = 1 << 5;
+
+const GPIO_V2_LINE_ATTR_ID_OUTPUT_VALUES: u32 = 2;
+
+const GPIO_V2_LINE_EVENT_RISING_EDGE: u32 = 1;
+const GPIO_V2_LINE_EVENT_FALLING_EDGE: u32 = 2;
+
+const CONSUMER: &[u8] = b"libbeaglebone";
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct gpio_v2_line_attribute {
+  id: u32,
+  padding: u32,
+  // A union of flags, values and debounce_period_us in the kernel.
+  value: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct gpio_v2_line_config_attribute {
+  attr: gpio_v2_line_attribute,
+  mask: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct gpio_v2_line_config {
+  flags: u64,
+  num_attrs: u32,
+  padding: [u32; 5],
+  attrs: [gpio_v2_line_config_attribute; GPIO_V2_LINE_NUM_ATTRS_MAX],
+}
+
+#[repr(C)]
+struct gpio_v2_line_request {
+  offsets: [u32; LINES_MAX],
+  consumer: [u8; 32],
+  config: gpio_v2_line_config,
+  num_lines: u32,
+  event_buffer_size: u32,
+  padding: [u32; 5],
+  fd: i32,
+}
+
+#[repr(C)]
+struct gpio_v2_line_values {
+  bits: u64,
+  mask: u64,
+}
+
+#[repr(C)]
+struct gpio_v2_line_event {
+  timestamp_ns: u64,
+  id: u32,
+  offset: u32,
+  seqno: u32,
+  line_seqno: u32,
+  padding: [u32; 6],
+}
+
+// IOCTL magic numbers
+const GPIO_IOC_MAGIC: u8 = 0xB4;
+const GPIO_V2_NR_GET_LINE: u8 = 0x07;
+const GPIO_V2_NR_LINE_SET_CONFIG: u8 = 0x0D;
+const GPIO_V2_NR_LINE_GET_VALUES: u8 = 0x0E;
+const GPIO_V2_NR_LINE_SET_VALUES: u8 = 0x0F;
+
+// These macros expand to the IOCTL wrapper functions needed to work with the
+// GPIO character devices.
+ioctl!(readwrite gpio_v2_get_line with GPIO_IOC_MAGIC, GPIO_V2_NR_GET_LINE; gpio_v2_line_request);
+ioctl!(readwrite gpio_v2_line_set_config with GPIO_IOC_MAGIC, GPIO_V2_NR_LINE_SET_CONFIG; gpio_v2_line_config);
+ioctl!(readwrite gpio_v2_line_get_values with GPIO_IOC_MAGIC, GPIO_V2_NR_LINE_GET_VALUES; gpio_v2_line_values);
+ioctl!(readwrite gpio_v2_line_set_values with GPIO_IOC_MAGIC, GPIO_V2_NR_LINE_SET_VALUES; gpio_v2_line_values);
+
+/// The configuration applied to every line of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineConfig {
+  /// Whether the lines are inputs or outputs.
+  pub direction: PinDirection,
+  /// The edges that generate events, only used by inputs.
+  pub edge: Edge,
+  /// The initial values of output lines, one bit per requested line.
+  pub output_values: u64,
+}
+
+impl Default for LineConfig {
+  fn default() -> LineConfig {
+    LineConfig {
+      direction: PinDirection::In,
+      edge: Edge::None,
+      output_values: 0,
+    }
+  }
+}
+
+impl LineConfig {
+  /// Converts the configuration to the kernel's representation.
+  fn to_raw(&self, num_lines: usize) -> gpio_v2_line_config {
+    let mut raw: gpio_v2_line_config = unsafe { mem::zeroed() };
+    match self.direction {
+      PinDirection::In => {
+        raw.flags = GPIO_V2_LINE_FLAG_INPUT |
+                    match self.edge {
+                      Edge::None => 0,
+                      Edge::Rising => GPIO_V2_LINE_FLAG_EDGE_RISING,
+                      Edge::Falling => GPIO_V2_LINE_FLAG_EDGE_FALLING,
+                      Edge::Both => GPIO_V2_LINE_FLAG_EDGE_RISING | GPIO_V2_LINE_FLAG_EDGE_FALLING,
+                    };
+      }
+      PinDirection::Out => {
+        raw.flags = GPIO_V2_LINE_FLAG_OUTPUT;
+        raw.num_attrs = 1;
+        raw.attrs[0].attr.id = GPIO_V2_LINE_ATTR_ID_OUTPUT_VALUES;
+        raw.attrs[0].attr.value = self.output_values;
+        raw.attrs[0].mask = line_mask(num_lines);
+      }
+    }
+    raw
+  }
+}
+
+/// An edge event reported by the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineEvent {
+  /// The edge that occurred, either `Edge::Rising` or `Edge::Falling`.
+  pub edge: Edge,
+  /// The `CLOCK_MONOTONIC` time at which the kernel saw the edge.
+  pub timestamp: Duration,
+  /// The offset of the line within its chip.
+  pub offset: u32,
+  /// The sequence number of the event across all lines of the request.
+  pub seqno: u32,
+  /// The sequence number of the event on this line.
+  pub line_seqno: u32,
+}
+
+/// Represents a set of lines on one GPIO chip requested from the kernel.
+///
+/// The lines are released when the request is dropped.
+#[derive(Debug)]
+pub struct LineRequest {
+  chip_path: PathBuf,
+  num_lines: usize,
+  line_file: File,
+}
+
+impl LineRequest {
+  /// Requests lines from a GPIO chip.
+  ///
+  /// Bit `i` of any value mask refers to the line at `offsets[i]`.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::gpiochip::{LineConfig, LineRequest};
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// // Request lines 12 and 13 of GPIO bank 1 as outputs, driving both low.
+  /// let board = Board::new();
+  /// let config = LineConfig {
+  ///   direction: PinDirection::Out,
+  ///   ..Default::default()
+  /// };
+  /// let lines = LineRequest::new(&board.devfs_path("gpiochip1"), &[12, 13], &config).unwrap();
+  ///
+  /// // Drive both lines high at once.
+  /// lines.set_values(0b11, 0b11).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if no offsets or more than `LINES_MAX` offsets are given.
+  /// Fails if the chip can't be opened, or if the kernel refuses the request,
+  /// e.g. because a line is already in use.
+  pub fn new(chip_path: &Path, offsets: &[u32], config: &LineConfig) -> Result<LineRequest> {
+    if offsets.is_empty() || offsets.len() > LINES_MAX {
+      bail!(ErrorKind::InvalidValue(
+        chip_name(chip_path),
+        format!("{} lines requested", offsets.len())
+      ));
+    }
+
+    let chip_file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .open(chip_path)
+      .map_err(|e| Error::from_io(e, &chip_name(chip_path), chip_path))?;
+
+    let mut req: gpio_v2_line_request = unsafe { mem::zeroed() };
+    req.offsets[..offsets.len()].copy_from_slice(offsets);
+    req.consumer[..CONSUMER.len()].copy_from_slice(CONSUMER);
+    req.config = config.to_raw(offsets.len());
+    req.num_lines = offsets.len() as u32;
+
+    unsafe {
+      let _ = gpio_v2_get_line(chip_file.as_raw_fd(), &mut req)
+        .map_err(|e| Error::from_nix(e, &chip_name(chip_path), chip_path))?;
+    };
+
+    Ok(LineRequest {
+      chip_path: chip_path.to_path_buf(),
+      num_lines: offsets.len(),
+      line_file: unsafe { File::from_raw_fd(req.fd) },
+    })
+  }
+
+  /// Returns the number of lines in the request.
+  pub fn num_lines(&self) -> usize {
+    self.num_lines
+  }
+
+  /// Changes the configuration of every line in the request.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel rejects the configuration.
+  pub fn set_config(&self, config: &LineConfig) -> Result<()> {
+    let mut raw = config.to_raw(self.num_lines);
+    unsafe {
+      let _ = gpio_v2_line_set_config(self.line_file.as_raw_fd(), &mut raw)
+        .map_err(|e| self.error(e))?;
+    };
+    Ok(())
+  }
+
+  /// Reads the values of the lines selected by `mask`.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel is unable to read the lines.
+  pub fn get_values(&self, mask: u64) -> Result<u64> {
+    let mut values = gpio_v2_line_values {
+      bits: 0,
+      mask: mask & line_mask(self.num_lines),
+    };
+    unsafe {
+      let _ = gpio_v2_line_get_values(self.line_file.as_raw_fd(), &mut values)
+        .map_err(|e| self.error(e))?;
+    };
+    Ok(values.bits & values.mask)
+  }
+
+  /// Sets the values of the lines selected by `mask` in a single operation.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the lines aren't outputs or if the kernel is unable to write to
+  /// them.
+  pub fn set_values(&self, bits: u64, mask: u64) -> Result<()> {
+    let mut values = gpio_v2_line_values {
+      bits: bits,
+      mask: mask & line_mask(self.num_lines),
+    };
+    unsafe {
+      let _ = gpio_v2_line_set_values(self.line_file.as_raw_fd(), &mut values)
+        .map_err(|e| self.error(e))?;
+    };
+    Ok(())
+  }
+
+  /// Blocks until an edge event is available and returns it.
+  ///
+  /// Pass `None` as the timeout to wait forever.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the timeout elapses before an event occurs.
+  /// Fails if the kernel is unable to report the event.
+  pub fn wait_event(&self, timeout: Option<Duration>) -> Result<LineEvent> {
+    if !poll_file(&self.line_file, POLLIN, timeout).map_err(|e| self.error(e))? {
+      bail!(ErrorKind::Timeout(chip_name(&self.chip_path)));
+    }
+    self.read_event()
+  }
+
+  /// Reads the next edge event, blocking if none is pending.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel is unable to report the event.
+  pub fn read_event(&self) -> Result<LineEvent> {
+    let mut buf = [0u8; 48];
+    (&self.line_file)
+      .read_exact(&mut buf)
+      .map_err(|e| Error::from_io(e, &chip_name(&self.chip_path), &self.chip_path))?;
+    let raw: gpio_v2_line_event = unsafe { ptr::read_unaligned(buf.as_ptr() as *const _) };
+
+    Ok(LineEvent {
+      edge: match raw.id {
+        GPIO_V2_LINE_EVENT_RISING_EDGE => Edge::Rising,
+        GPIO_V2_LINE_EVENT_FALLING_EDGE => Edge::Falling,
+        id => bail!(ErrorKind::InvalidValue(chip_name(&self.chip_path), format!("event id {}", id))),
+      },
+      timestamp: Duration::new(
+        raw.timestamp_ns / 1_000_000_000,
+        (raw.timestamp_ns % 1_000_000_000) as u32,
+      ),
+      offset: raw.offset,
+      seqno: raw.seqno,
+      line_seqno: raw.line_seqno,
+    })
+  }
+
+  /// Wraps an error from a system call on the line request.
+  fn error(&self, err: ::nix::Error) -> Error {
+    Error::from_nix(err, &chip_name(&self.chip_path), &self.chip_path)
+  }
+}
+
+/// Returns the device name of a chip, e.g. `gpiochip1`, or its whole path if
+/// it has none.
+fn chip_name(chip_path: &Path) -> String {
+  match chip_path.file_name() {
+    Some(name) => name.to_string_lossy().into_owned(),
+    None => chip_path.display().to_string(),
+  }
+}
+
+/// Returns a mask selecting the first `num_lines` lines.
+fn line_mask(num_lines: usize) -> u64 {
+  if num_lines >= LINES_MAX {
+    !0
+  } else {
+    (1 << num_lines) - 1
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::mem::size_of;
+
+  // These must match the kernel's `linux/gpio.h` exactly, or the ioctls
+  // read and write past the end of the structs.
+  #[test]
+  fn uapi_struct_sizes() {
+    assert_eq!(size_of::<gpio_v2_line_attribute>(), 16);
+    assert_eq!(size_of::<gpio_v2_line_config_attribute>(), 24);
+    assert_eq!(size_of::<gpio_v2_line_config>(), 272);
+    assert_eq!(size_of::<gpio_v2_line_request>(), 592);
+    assert_eq!(size_of::<gpio_v2_line_values>(), 16);
+    assert_eq!(size_of::<gpio_v2_line_event>(), 48);
+  }
+}
@@ -0,0 +1,116 @@
+//! The embedded-hal module.
+//!
+//! With the `hal` feature enabled, the peripherals implement the traits of
+//! [`embedded-hal`](https://docs.rs/embedded-hal/1.0.0) and
+//! [`embedded-io`](https://docs.rs/embedded-io/0.6.1), so drivers written
+//! against those traits can be used on the BeagleBone:
+//!
+//! * `GPIO`: `OutputPin`, `InputPin` and `StatefulOutputPin`
+//! * `PWM`: `SetDutyCycle`
+//! * `I2C`: `I2c`
+//! * `SPI`: `SpiBus` and `SpiDevice`
+//! * `UART`: `embedded_io::Read` and `embedded_io::Write`
+//! * `Delay`: `DelayNs`
+//!
+//! embedded-hal 1.0 has no ADC trait, so `ADC` has nothing to implement.
+//!
+//! All of the implementations use this crate's `Error`, which reports an
+//! embedded-hal error kind where one matches.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate embedded_hal;
+//! extern crate libbeaglebone;
+//!
+//! use embedded_hal::delay::DelayNs;
+//! use embedded_hal::digital::OutputPin;
+//! use libbeaglebone::hal::Delay;
+//! use libbeaglebone::prelude::*;
+//!
+//! // A "driver" that only knows about embedded-hal.
+//! fn blink<P: OutputPin, D: DelayNs>(led: &mut P, delay: &mut D) -> Result<(), P::Error> {
+//!   led.set_high()?;
+//!   delay.delay_ms(500);
+//!   led.set_low()?;
+//!   delay.delay_ms(500);
+//!   Ok(())
+//! }
+//!
+//! fn main() {
+//!   let led = GPIO::new(&Board::new(), P8_11).unwrap();
+//!   let mut led = led.into_output(PinState::Low).unwrap();
+//!
+//!   blink(&mut led, &mut Delay).unwrap();
+//! }
+//! ```
+
+use embedded_hal::{digital, i2c, pwm, spi};
+use embedded_hal::delay::DelayNs;
+use embedded_io;
+use errors::*;
+use std::thread;
+use std::time::Duration;
+
+/// A delay provider that puts the thread to sleep.
+///
+/// Sleeping lasts at least as long as requested, but the scheduler may add
+/// anywhere from microseconds to milliseconds on top of that.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Delay;
+
+impl DelayNs for Delay {
+  fn delay_ns(&mut self, ns: u32) {
+    thread::sleep(Duration::new(0, ns));
+  }
+
+  fn delay_us(&mut self, us: u32) {
+    thread::sleep(Duration::from_micros(us as u64));
+  }
+
+  fn delay_ms(&mut self, ms: u32) {
+    thread::sleep(Duration::from_millis(ms as u64));
+  }
+}
+
+impl digital::Error for Error {
+  fn kind(&self) -> digital::ErrorKind {
+    digital::ErrorKind::Other
+  }
+}
+
+impl pwm::Error for Error {
+  fn kind(&self) -> pwm::ErrorKind {
+    pwm::ErrorKind::Other
+  }
+}
+
+impl i2c::Error for Error {
+  fn kind(&self) -> i2c::ErrorKind {
+    match *self.kind() {
+      ErrorKind::NoAcknowledge(..) => {
+        i2c::ErrorKind::NoAcknowledge(i2c::NoAcknowledgeSource::Unknown)
+      }
+      _ => i2c::ErrorKind::Other,
+    }
+  }
+}
+
+impl spi::Error for Error {
+  fn kind(&self) -> spi::ErrorKind {
+    spi::ErrorKind::Other
+  }
+}
+
+impl embedded_io::Error for Error {
+  fn kind(&self) -> embedded_io::ErrorKind {
+    match *self.kind() {
+      ErrorKind::NotExported(..) => embedded_io::ErrorKind::NotFound,
+      ErrorKind::PermissionDenied(..) => embedded_io::ErrorKind::PermissionDenied,
+      ErrorKind::InvalidPin(..) |
+      ErrorKind::InvalidValue(..) => embedded_io::ErrorKind::InvalidInput,
+      ErrorKind::Timeout(..) => embedded_io::ErrorKind::TimedOut,
+      _ => embedded_io::ErrorKind::Other,
+    }
+  }
+}
@@ -8,24 +8,186 @@
 //! This command works for recent kernel versions.
 //! If you wish to enable another I2C, substitute its number for 1 in the
 //! command above.
+//!
+//! `read()` and `write()` each send a single message to the slave set with
+//! `set_slave_address()`, ending with a stop condition.
+//! `write_read()` sends a write and a read to that slave joined by a repeated
+//! start, which many sensors need for register reads, and `transaction()`
+//! sends any number of messages, possibly to different slaves, the same way.
+//!
+//! For SMBus devices, `read_byte_data()`, `write_word_data()` and the other
+//! SMBus helpers send the protocol's commands to the slave set with
+//! `set_slave_address()`, optionally with packet error checking (see
+//! `set_pec()`).
 
+use board::Board;
 use errors::*;
+use nix;
+use pinmux::{self, PinMode};
+use pins::Pin;
+use std::cell::Cell;
 use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
 use std::os::unix::io::AsRawFd;
-use util::*;
+use std::path::PathBuf;
 
 /// Magic I2C numbers
 const I2C_SLAVE: u16 = 0x0703;
+const I2C_RDWR: u16 = 0x0707;
+const I2C_PEC: u16 = 0x0708;
+const I2C_SMBUS: u16 = 0x0720;
+
+/// The message flag for reads, from linux/i2c.h.
+const I2C_M_RD: u16 = 0x0001;
+/// The most messages the kernel accepts in one transaction.
+const I2C_RDWR_MAX_MSGS: usize = 42;
+
+// SMBus transfer directions and sizes, from linux/i2c.h
+const I2C_SMBUS_READ: u8 = 1;
+const I2C_SMBUS_WRITE: u8 = 0;
+const I2C_SMBUS_BYTE_DATA: u32 = 2;
+const I2C_SMBUS_WORD_DATA: u32 = 3;
+const I2C_SMBUS_PROC_CALL: u32 = 4;
+const I2C_SMBUS_BLOCK_DATA: u32 = 5;
+const I2C_SMBUS_I2C_BLOCK_DATA: u32 = 8;
+/// The most data bytes in an SMBus block transfer.
+const I2C_SMBUS_BLOCK_MAX: usize = 32;
 
 // These macros expand to the nice IOCTL wrapper functions needed to work with
 // the i2cdev system.
 ioctl!(ioctl_set_i2c_slave_addr with I2C_SLAVE);
+ioctl!(ioctl_i2c_rdwr with I2C_RDWR);
+ioctl!(ioctl_set_i2c_pec with I2C_PEC);
+ioctl!(ioctl_i2c_smbus with I2C_SMBUS);
+
+// Constants extracted from linux/i2c.h
+bitflags! {
+  /// Options for a message in an I2C transaction.
+  pub struct MessageFlags: u16 {
+    /// The address is a 10-bit address.
+    const I2C_M_TEN = 0x0010;
+    /// The message continues the previous one, without a repeated start or
+    /// address. Not all adapters support this.
+    const I2C_M_NOSTART = 0x4000;
+  }
+}
+
+/// A message in an I2C transaction: a read from or a write to one slave.
+#[derive(Debug)]
+pub struct Message<'a> {
+  addr: u16,
+  flags: u16,
+  len: usize,
+  buf: *mut u8,
+
+  buf_ref: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> Message<'a> {
+  /// Creates a message reading from the slave at `address`, filling `buf`.
+  pub fn read(address: u16, buf: &'a mut [u8]) -> Message<'a> {
+    Message {
+      addr: address,
+      flags: I2C_M_RD,
+      len: buf.len(),
+      buf: buf.as_mut_ptr(),
+      buf_ref: PhantomData,
+    }
+  }
+
+  /// Creates a message writing `data` to the slave at `address`.
+  pub fn write(address: u16, data: &'a [u8]) -> Message<'a> {
+    Message {
+      addr: address,
+      flags: 0,
+      len: data.len(),
+      // The kernel only reads from the buffers of write messages
+      buf: data.as_ptr() as *mut u8,
+      buf_ref: PhantomData,
+    }
+  }
+
+  /// Adds options to the message.
+  pub fn with_flags(mut self, flags: MessageFlags) -> Message<'a> {
+    self.flags |= flags.bits();
+    self
+  }
+
+  /// Returns the address of the slave the message is for.
+  pub fn address(&self) -> u16 {
+    self.addr
+  }
+
+  /// Returns whether the message reads from the slave.
+  pub fn is_read(&self) -> bool {
+    self.flags & I2C_M_RD != 0
+  }
+}
+
+/// A message as passed to the kernel, matching its `struct i2c_msg`.
+#[repr(C)]
+struct I2CMsg {
+  addr: u16,
+  flags: u16,
+  len: u16,
+  buf: *mut u8,
+}
+
+/// The argument of the `I2C_RDWR` ioctl, matching the kernel's
+/// `struct i2c_rdwr_ioctl_data`.
+#[repr(C)]
+struct RdwrIoctlData {
+  msgs: *mut I2CMsg,
+  nmsgs: u32,
+}
+
+/// The data of an SMBus transfer, matching the kernel's
+/// `union i2c_smbus_data`: a byte, a word, or a block whose first byte is its
+/// length.
+#[repr(C)]
+struct SmbusData {
+  block: [u8; I2C_SMBUS_BLOCK_MAX + 2],
+}
+
+impl SmbusData {
+  /// Creates transfer data holding a byte.
+  fn byte(value: u8) -> SmbusData {
+    let mut data = SmbusData { block: [0; I2C_SMBUS_BLOCK_MAX + 2] };
+    data.block[0] = value;
+    data
+  }
+
+  /// Creates transfer data holding a word.
+  fn word(value: u16) -> SmbusData {
+    let mut data = SmbusData::byte(0);
+    data.block[..2].copy_from_slice(&value.to_ne_bytes());
+    data
+  }
+
+  /// Returns the word held by the transfer data.
+  fn as_word(&self) -> u16 {
+    u16::from_ne_bytes([self.block[0], self.block[1]])
+  }
+}
+
+/// The argument of the `I2C_SMBUS` ioctl, matching the kernel's
+/// `struct i2c_smbus_ioctl_data`.
+#[repr(C)]
+struct SmbusIoctlData {
+  read_write: u8,
+  command: u8,
+  size: u32,
+  data: *mut SmbusData,
+}
 
 /// Represents and I2C interface.
 #[derive(Debug)]
 pub struct I2C {
   i2c_num: u8,
+  i2c_path: PathBuf,
   i2c_file: File,
+  slave_addr: Cell<Option<u16>>,
 }
 
 impl I2C {
@@ -39,21 +201,29 @@ impl I2C {
   /// // Create a new I2C interface using BB_I2C1.
   /// // Don't forget to enable the I2C beforehand using bone_capemgr.
   /// // Consult the module documentation for more information!
-  /// let i2c = I2C::new(1).unwrap();
+  /// let i2c = I2C::new(&Board::new(), 1).unwrap();
   /// ```
   ///
   /// # Errors
   ///
   /// Method fails if `i2c_num` is an invalid I2C port (i.e. isn't within 1-2)
   /// or if the kernel fails to open the port for some other reason.
-  pub fn new(i2c_num: u8) -> Result<(I2C)> {
+  pub fn new(board: &Board, i2c_num: u8) -> Result<(I2C)> {
+    if board.auto_pinmux() {
+      for &pin in i2c_pins(i2c_num) {
+        pinmux::set_mode(board, pin, PinMode::I2C)?;
+      }
+    }
+    let i2c_path = board.devfs_path(format!("i2c-{}", i2c_num));
     Ok(I2C {
       i2c_num: i2c_num,
       i2c_file: OpenOptions::new()
         .read(true)
         .write(true)
-        .open(&format!("/dev/i2c-{}", i2c_num))
-        .chain_err(|| format!("Failed to open new I2C device #{}.", i2c_num))?,
+        .open(&i2c_path)
+        .map_err(|e| Error::from_io(e, &format!("I2C #{}", i2c_num), &i2c_path))?,
+      i2c_path: i2c_path,
+      slave_addr: Cell::new(None),
     })
   }
 
@@ -65,7 +235,7 @@ impl I2C {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new I2C interface using BB_I2C1.
-  /// let i2c = I2C::new(1).unwrap();
+  /// let i2c = I2C::new(&Board::new(), 1).unwrap();
   ///
   /// // Set the slave address to 0x45.
   /// i2c.set_slave_address(0x45).unwrap();
@@ -78,14 +248,13 @@ impl I2C {
   pub fn set_slave_address(&self, slave_addr: u16) -> Result<()> {
     unsafe {
       let _ = ioctl_set_i2c_slave_addr(self.i2c_file.as_raw_fd(), slave_addr as *mut u8)
-        .chain_err(|| {
-          format!("Failed to set I2C slave device address to {}.", slave_addr)
-        })?;
-      Ok(())
+        .map_err(|e| Error::from_nix(e, &self.name(), &self.i2c_path))?;
     }
+    self.slave_addr.set(Some(slave_addr));
+    Ok(())
   }
 
-  /// Writes a single byte to an I2C slave.
+  /// Writes bytes to the I2C slave in a single message.
   ///
   /// # Examples
   ///
@@ -93,23 +262,25 @@ impl I2C {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new I2C interface using BB_I2C1.
-  /// let i2c = I2C::new(1).unwrap();
+  /// let mut i2c = I2C::new(&Board::new(), 1).unwrap();
   ///
   /// // Set the slave address to 0x45.
   /// i2c.set_slave_address(0x45).unwrap();
   ///
-  /// // Write a 1 to the I2C slave
-  /// i2c.write(1).unwrap();
+  /// // Write 0x80 to the slave's register 0x01
+  /// i2c.write(&[0x01, 0x80]).unwrap();
   /// ```
   ///
   /// # Errors
   ///
-  /// Fails if the kernel is unable to write the chosen value to the device.
-  pub fn write(self, data: u8) -> Result<()> {
-    Ok(self.i2c_file.write_file(&(data.to_string()))?)
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge its address
+  /// or the data.
+  /// Fails if the kernel is unable to write to the device.
+  pub fn write(&mut self, data: &[u8]) -> Result<()> {
+    self.i2c_file.write_all(data).map_err(|e| self.error(e))
   }
 
-  /// Reads a single byte from an I2C slave and returns it.
+  /// Reads bytes from the I2C slave in a single message, filling `buf`.
   ///
   /// # Examples
   ///
@@ -117,20 +288,391 @@ impl I2C {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new I2C interface using BB_I2C1.
-  /// let mut i2c = I2C::new(1).unwrap();
+  /// let mut i2c = I2C::new(&Board::new(), 1).unwrap();
   ///
   /// // Set the slave address to 0x45.
   /// i2c.set_slave_address(0x45).unwrap();
   ///
-  /// // Read some data from the I2C device and display it.
-  /// println!("Read {} from the I2C slave!", i2c.read().unwrap());
+  /// // Read two bytes from the I2C slave and display them.
+  /// let mut buf = [0; 2];
+  /// i2c.read(&mut buf).unwrap();
+  /// println!("Read {:?} from the I2C slave!", buf);
   /// ```
   ///
   /// # Errors
   ///
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge its address.
   /// Fails if the kernel is unable to read from the device.
-  pub fn read(self) -> Result<(u8)> {
-    let res = self.i2c_file.read_file()?;
-    Ok(res.trim().parse::<u8>().unwrap())
+  pub fn read(&mut self, buf: &mut [u8]) -> Result<()> {
+    self.i2c_file.read_exact(buf).map_err(|e| self.error(e))
+  }
+
+  /// Writes bytes to the I2C slave, then reads from it, filling `buf`, as one
+  /// transaction joined by a repeated start.
+  ///
+  /// This is how most devices' registers are read: the write sets the
+  /// register pointer, and the read starts from that register. The repeated
+  /// start keeps another master from moving the pointer in between.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// // Create a new I2C interface using BB_I2C1.
+  /// let mut i2c = I2C::new(&Board::new(), 1).unwrap();
+  ///
+  /// // Set the slave address to 0x45.
+  /// i2c.set_slave_address(0x45).unwrap();
+  ///
+  /// // Read the slave's 16-bit big-endian register 0x02
+  /// let mut buf = [0; 2];
+  /// i2c.write_read(&[0x02], &mut buf).unwrap();
+  /// let value = (buf[0] as u16) << 8 | buf[1] as u16;
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if no slave address was set, or if `data` or
+  /// `buf` is longer than 65535 bytes.
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge its address
+  /// or the data.
+  /// Fails if the kernel is unable to perform the transaction.
+  pub fn write_read(&mut self, data: &[u8], buf: &mut [u8]) -> Result<()> {
+    let address = match self.slave_addr.get() {
+      Some(address) => address,
+      None => bail!(ErrorKind::InvalidValue(self.name(), "no slave address".to_string())),
+    };
+    self.transaction(&[Message::write(address, data), Message::read(address, buf)])
+  }
+
+  /// Sends several messages as one transaction, joined by repeated starts
+  /// and ended by a single stop condition.
+  ///
+  /// Each message carries its own slave address, so the slave address set
+  /// with `set_slave_address()` isn't used or changed.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::i2c::{I2C_M_TEN, Message};
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut i2c = I2C::new(&Board::new(), 2).unwrap();
+  ///
+  /// // Read the 16-bit register 0x02 of the slave at 0x45 with a repeated
+  /// // start, so no other master can change the register pointer
+  /// let mut buf = [0; 2];
+  /// i2c.transaction(&[Message::write(0x45, &[0x02]), Message::read(0x45, &mut buf)])
+  ///    .unwrap();
+  ///
+  /// // Write to a slave with a 10-bit address
+  /// i2c.transaction(&[Message::write(0x2a5, &[0x01, 0xff]).with_flags(I2C_M_TEN)])
+  ///    .unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if there are more than 42 messages, or if a
+  /// message is longer than 65535 bytes.
+  /// Fails with `NoAcknowledge` if a slave doesn't acknowledge its address or
+  /// the data.
+  /// Fails if the adapter doesn't support the messages' flags, or the kernel
+  /// is unable to perform the transaction.
+  pub fn transaction(&mut self, messages: &[Message]) -> Result<()> {
+    if messages.is_empty() {
+      return Ok(());
+    }
+    if messages.len() > I2C_RDWR_MAX_MSGS {
+      bail!(ErrorKind::InvalidValue(self.name(), format!("{} messages", messages.len())));
+    }
+    let mut msgs = Vec::with_capacity(messages.len());
+    for message in messages.iter() {
+      if message.len > u16::max_value() as usize {
+        bail!(ErrorKind::InvalidValue(self.name(), format!("message of {} bytes", message.len)));
+      }
+      msgs.push(I2CMsg {
+        addr: message.addr,
+        flags: message.flags,
+        len: message.len as u16,
+        buf: message.buf,
+      });
+    }
+    let mut data = RdwrIoctlData {
+      msgs: msgs.as_mut_ptr(),
+      nmsgs: msgs.len() as u32,
+    };
+    let data_ptr: *mut RdwrIoctlData = &mut data;
+    unsafe {
+      let _ = ioctl_i2c_rdwr(self.i2c_file.as_raw_fd(), data_ptr as *mut u8)
+        .map_err(|e| self.error(io::Error::from(e)))?;
+    }
+    Ok(())
+  }
+
+  /// Enables or disables SMBus packet error checking (PEC).
+  ///
+  /// With PEC enabled, SMBus transfers end with a CRC-8 of the whole
+  /// transfer, which the slave checks on writes and the kernel checks on
+  /// reads.
+  /// The slave must support PEC.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the kernel is unable to change the setting.
+  pub fn set_pec(&mut self, enabled: bool) -> Result<()> {
+    unsafe {
+      let _ = ioctl_set_i2c_pec(self.i2c_file.as_raw_fd(), enabled as usize as *mut u8)
+        .map_err(|e| Error::from_nix(e, &self.name(), &self.i2c_path))?;
+      Ok(())
+    }
+  }
+
+  /// Reads a byte from one of the slave's registers (SMBus read byte).
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut i2c = I2C::new(&Board::new(), 2).unwrap();
+  /// i2c.set_slave_address(0x48).unwrap();
+  ///
+  /// // Read the configuration register, and set its shutdown bit
+  /// let config = i2c.read_byte_data(0x01).unwrap();
+  /// i2c.write_byte_data(0x01, config | 0x01).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge.
+  /// Fails if PEC is enabled and the checksum doesn't match, or the kernel is
+  /// unable to perform the transfer.
+  pub fn read_byte_data(&mut self, command: u8) -> Result<u8> {
+    let mut data = SmbusData::byte(0);
+    self.smbus(I2C_SMBUS_READ, command, I2C_SMBUS_BYTE_DATA, &mut data)?;
+    Ok(data.block[0])
+  }
+
+  /// Writes a byte to one of the slave's registers (SMBus write byte).
+  ///
+  /// # Errors
+  ///
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge.
+  /// Fails if the kernel is unable to perform the transfer.
+  pub fn write_byte_data(&mut self, command: u8, value: u8) -> Result<()> {
+    self.smbus(I2C_SMBUS_WRITE, command, I2C_SMBUS_BYTE_DATA, &mut SmbusData::byte(value))
+  }
+
+  /// Reads a 16-bit word from one of the slave's registers (SMBus read
+  /// word).
+  ///
+  /// SMBus sends the low byte first; devices that send the high byte first
+  /// need the result's bytes swapped with `swap_bytes()`.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge.
+  /// Fails if PEC is enabled and the checksum doesn't match, or the kernel is
+  /// unable to perform the transfer.
+  pub fn read_word_data(&mut self, command: u8) -> Result<u16> {
+    let mut data = SmbusData::word(0);
+    self.smbus(I2C_SMBUS_READ, command, I2C_SMBUS_WORD_DATA, &mut data)?;
+    Ok(data.as_word())
+  }
+
+  /// Writes a 16-bit word to one of the slave's registers (SMBus write
+  /// word), low byte first.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge.
+  /// Fails if the kernel is unable to perform the transfer.
+  pub fn write_word_data(&mut self, command: u8, value: u16) -> Result<()> {
+    self.smbus(I2C_SMBUS_WRITE, command, I2C_SMBUS_WORD_DATA, &mut SmbusData::word(value))
+  }
+
+  /// Sends a word to the slave and reads back its reply (SMBus process
+  /// call).
+  ///
+  /// # Errors
+  ///
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge.
+  /// Fails if PEC is enabled and the checksum doesn't match, or the kernel is
+  /// unable to perform the transfer.
+  pub fn process_call(&mut self, command: u8, value: u16) -> Result<u16> {
+    let mut data = SmbusData::word(value);
+    self.smbus(I2C_SMBUS_WRITE, command, I2C_SMBUS_PROC_CALL, &mut data)?;
+    Ok(data.as_word())
+  }
+
+  /// Reads a block of up to 32 bytes whose length is sent by the slave
+  /// (SMBus block read).
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut i2c = I2C::new(&Board::new(), 2).unwrap();
+  ///
+  /// // Read a smart battery's manufacturer name, with packet error checking
+  /// i2c.set_slave_address(0x0b).unwrap();
+  /// i2c.set_pec(true).unwrap();
+  /// let name = i2c.read_block_data(0x20).unwrap();
+  /// println!("Battery made by {}", String::from_utf8_lossy(&name));
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge.
+  /// Fails if PEC is enabled and the checksum doesn't match, or the kernel is
+  /// unable to perform the transfer.
+  /// Fails with `InvalidValue` if the slave sends an invalid length.
+  pub fn read_block_data(&mut self, command: u8) -> Result<Vec<u8>> {
+    let mut data = SmbusData::byte(0);
+    self.smbus(I2C_SMBUS_READ, command, I2C_SMBUS_BLOCK_DATA, &mut data)?;
+    let len = data.block[0] as usize;
+    if len > I2C_SMBUS_BLOCK_MAX {
+      bail!(ErrorKind::InvalidValue(self.name(), format!("block of {} bytes", len)));
+    }
+    Ok(data.block[1..len + 1].to_vec())
+  }
+
+  /// Reads 1 to 32 bytes from the slave's registers starting at `command`,
+  /// filling `buf`.
+  ///
+  /// Unlike `read_block_data()`, the slave doesn't send the length, so this
+  /// works with plain I2C devices that auto-increment their register
+  /// pointer.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if `buf` is empty or longer than 32 bytes, or
+  /// if fewer bytes than requested were read.
+  /// Fails with `NoAcknowledge` if the slave doesn't acknowledge.
+  /// Fails if the kernel is unable to perform the transfer.
+  pub fn read_i2c_block_data(&mut self, command: u8, buf: &mut [u8]) -> Result<()> {
+    if buf.is_empty() || buf.len() > I2C_SMBUS_BLOCK_MAX {
+      bail!(ErrorKind::InvalidValue(self.name(), format!("block of {} bytes", buf.len())));
+    }
+    let mut data = SmbusData::byte(buf.len() as u8);
+    self.smbus(I2C_SMBUS_READ, command, I2C_SMBUS_I2C_BLOCK_DATA, &mut data)?;
+    // The kernel replaces the length with the number of bytes it read
+    let read = data.block[0] as usize;
+    if read < buf.len() {
+      bail!(ErrorKind::InvalidValue(self.name(),
+                                    format!("read {} of {} bytes", read, buf.len())));
+    }
+    buf.copy_from_slice(&data.block[1..buf.len() + 1]);
+    Ok(())
+  }
+
+  /// Performs an SMBus transfer with the slave.
+  fn smbus(&mut self, read_write: u8, command: u8, size: u32, data: &mut SmbusData) -> Result<()> {
+    let mut args = SmbusIoctlData {
+      read_write: read_write,
+      command: command,
+      size: size,
+      data: data,
+    };
+    let args_ptr: *mut SmbusIoctlData = &mut args;
+    unsafe {
+      let _ = ioctl_i2c_smbus(self.i2c_file.as_raw_fd(), args_ptr as *mut u8)
+        .map_err(|e| self.error(io::Error::from(e)))?;
+    }
+    Ok(())
+  }
+
+  fn name(&self) -> String {
+    format!("I2C #{}", self.i2c_num)
+  }
+
+  /// Wraps an error that occurred while talking to the slave.
+  fn error(&self, err: io::Error) -> Error {
+    match err.raw_os_error().map(nix::Errno::from_i32) {
+      // The adapter reports a missing acknowledge as one of these
+      Some(nix::Errno::ENXIO) |
+      Some(nix::Errno::EREMOTEIO) => Error::with_chain(err, ErrorKind::NoAcknowledge(self.name())),
+      _ => Error::from_io(err, &self.name(), &self.i2c_path),
+    }
+  }
+}
+
+/// Returns the header pins of an I2C bus (SCL first), which are muxed when
+/// the board muxes pins.
+fn i2c_pins(i2c_num: u8) -> &'static [Pin] {
+  match i2c_num {
+    1 => &[Pin::P9_17, Pin::P9_18],
+    2 => &[Pin::P9_19, Pin::P9_20],
+    _ => &[],
+  }
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::i2c::ErrorType for I2C {
+  type Error = Error;
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::i2c::I2c for I2C {
+  /// Performs the operations on the slave at `address` as one transaction,
+  /// joined by repeated starts.
+  ///
+  /// Adjacent operations of the same kind are merged into one message, as
+  /// the BeagleBone's adapter doesn't support `I2C_M_NOSTART`.
+  fn transaction(&mut self,
+                 address: u8,
+                 operations: &mut [::embedded_hal::i2c::Operation])
+                 -> Result<()> {
+    use embedded_hal::i2c::Operation;
+
+    // Gather each run of reads or writes into a single buffer
+    let mut runs: Vec<(bool, Vec<u8>)> = Vec::new();
+    for op in operations.iter() {
+      let (is_read, len, data) = match *op {
+        Operation::Read(ref buf) => (true, buf.len(), &[][..]),
+        Operation::Write(buf) => (false, buf.len(), buf),
+      };
+      let new_run = runs.last().map_or(true, |&(run_is_read, _)| run_is_read != is_read);
+      if new_run {
+        runs.push((is_read, Vec::new()));
+      }
+      let run = &mut runs.last_mut().unwrap().1;
+      if is_read {
+        run.resize(run.len() + len, 0);
+      } else {
+        run.extend_from_slice(data);
+      }
+    }
+
+    {
+      let messages: Vec<Message> = runs.iter_mut()
+                                           .map(|&mut (is_read, ref mut buf)| {
+                                             if is_read {
+                                               Message::read(address as u16, buf)
+                                             } else {
+                                               Message::write(address as u16, buf)
+                                             }
+                                           })
+                                           .collect();
+      self.transaction(&messages)?;
+    }
+
+    // Hand the bytes read out to the operations they belong to
+    let mut reads = runs.into_iter().filter(|&(is_read, _)| is_read).map(|(_, buf)| buf);
+    let mut run = Vec::new();
+    let mut pos = 0;
+    for op in operations.iter_mut() {
+      if let Operation::Read(ref mut buf) = *op {
+        if pos == run.len() {
+          run = reads.next().unwrap_or_default();
+          pos = 0;
+        }
+        buf.copy_from_slice(&run[pos..pos + buf.len()]);
+        pos += buf.len();
+      }
+    }
+    Ok(())
   }
 }
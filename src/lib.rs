@@ -38,18 +38,32 @@
 #[macro_use] extern crate bitflags;
 #[macro_use] extern crate error_chain;
 #[macro_use] extern crate nix;
+extern crate libc;
 extern crate serialport;
+#[cfg(feature = "hal")] extern crate embedded_hal;
+#[cfg(feature = "hal")] extern crate embedded_io;
 
+pub mod board;
 pub mod gpio;
+pub mod gpiochip;
+pub mod mmap_gpio;
 pub mod enums;
 pub mod errors;
 pub mod pwm;
+pub mod ecap;
+pub mod eqep;
 pub mod util;
 pub mod adc;
+pub mod adc_buffer;
+pub mod adc_filter;
 pub mod uart;
 pub mod i2c;
 pub mod spi;
 pub mod pins;
+pub mod pinmux;
+pub mod ramp;
+pub mod servo;
+#[cfg(feature = "hal")] pub mod hal;
 
 /// Exports types that might be useful to have in scope.
 ///
@@ -60,10 +74,13 @@ pub mod pins;
 /// ```
 pub mod prelude {
   pub use adc::ADC;
-  pub use enums::DeviceState;
-  pub use gpio::{GPIO, PinDirection, PinState};
+  pub use board::Board;
+  pub use enums::{DeviceState, SafeState};
+  pub use gpio::{Edge, GPIO, GPIOBackend, GPIOGroup, Input, Output, PinDirection, PinState,
+                 Unexported};
   pub use i2c::I2C;
-  pub use pwm::{PWM, PWMState};
+  pub use pwm::{PWM, PWMKind, PWMState, Polarity};
   pub use uart::UART;
   pub use pins::Pin::*;
+  pub use pins::aliases::*;
 }
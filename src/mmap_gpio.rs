@@ -0,0 +1,225 @@
+//! The memory-mapped GPIO module.
+//!
+//! Every `GPIO::write()` goes through the kernel, which limits toggling
+//! speed to a few kHz.
+//! For bit-banging, `MappedGPIO` maps the registers of the AM335x's four GPIO
+//! banks into the process through `/dev/mem` and pokes them directly, which
+//! is orders of magnitude faster.
+//!
+//! This bypasses the kernel entirely, so creating a `MappedGPIO` is `unsafe`:
+//! the kernel won't stop you from driving a pin that's muxed to another
+//! function or owned by another driver.
+//! The pins should be configured as GPIOs (e.g. with `config-pin`) first, and
+//! accessing `/dev/mem` requires root.
+//!
+//! Pins are addressed by their GPIO number `n`, which lives in bank `n / 32`
+//! at bit `n % 32`.
+//! A `MappedPin` works that out once, checking that the pin has a GPIO
+//! number (e.g. isn't a power pin), so the accessors have nothing left to
+//! check.
+
+use board::Board;
+use errors::*;
+use gpio::{PinDirection, PinState};
+use nix::fcntl::O_SYNC;
+use nix::sys::mman::{MAP_SHARED, PROT_READ, PROT_WRITE, mmap, munmap};
+use pins::Pin;
+use std::fs::OpenOptions;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+
+/// The physical base addresses of the GPIO0-3 register banks.
+const GPIO_BANK_BASES: [usize; 4] = [0x44E0_7000, 0x4804_C000, 0x481A_C000, 0x481A_E000];
+/// The size of the mapping of each GPIO bank.
+const GPIO_BANK_SIZE: usize = 0x1000;
+
+// Register offsets, from the AM335x technical reference manual
+const GPIO_OE: usize = 0x134;
+const GPIO_DATAIN: usize = 0x138;
+const GPIO_DATAOUT: usize = 0x13C;
+const GPIO_CLEARDATAOUT: usize = 0x190;
+const GPIO_SETDATAOUT: usize = 0x194;
+
+/// A header pin's GPIO register bank and bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedPin {
+  bank: usize,
+  bit: u32,
+}
+
+impl MappedPin {
+  /// Looks up the GPIO register bank and bit of a header pin.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidPin` if the pin can't be used as a GPIO.
+  pub fn new(pin: Pin) -> Result<MappedPin> {
+    match pin.gpio_num() {
+      Some(pin_num) => {
+        Ok(MappedPin {
+          bank: (pin_num / 32) as usize,
+          bit: (pin_num % 32) as u32,
+        })
+      }
+      None => bail!(ErrorKind::InvalidPin(format!("{} can't be used as a GPIO", pin))),
+    }
+  }
+}
+
+/// Represents the memory-mapped registers of all four GPIO banks.
+#[derive(Debug)]
+pub struct MappedGPIO {
+  banks: [*mut u32; 4],
+}
+
+// The registers are plain memory, so the mapping can be moved to another
+// thread.
+unsafe impl Send for MappedGPIO {}
+
+impl MappedGPIO {
+  /// Maps the GPIO registers through the board's `/dev/mem`.
+  ///
+  /// Any file standing in for `/dev/mem` that is large enough to cover the
+  /// GPIO banks can be used through a `Board` with a custom devfs root.
+  ///
+  /// # Safety
+  ///
+  /// The caller must make sure the pins it touches are muxed as GPIOs and
+  /// aren't in use by a kernel driver.
+  /// `set_direction()` does a read-modify-write of the `GPIO_OE` register,
+  /// which can race with the kernel or other processes configuring pins in
+  /// the same bank.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::mmap_gpio::{MappedGPIO, MappedPin};
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut gpio = unsafe { MappedGPIO::new(&Board::new()).unwrap() };
+  ///
+  /// // GPIO #45 is bit 13 of bank 1, so this writes 1 << 13 to the
+  /// // GPIO_SETDATAOUT register of GPIO1.
+  /// gpio.set(MappedPin::new(P8_11).unwrap());
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if `/dev/mem` can't be opened (usually due to a lack of
+  /// permissions) or if the kernel refuses to map the registers.
+  pub unsafe fn new(board: &Board) -> Result<MappedGPIO> {
+    let mem_path = board.devfs_path("mem");
+    let mem_file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .custom_flags(O_SYNC.bits())
+      .open(&mem_path)
+      .map_err(|e| Error::from_io(e, "GPIO registers", &mem_path))?;
+
+    let mut banks = [ptr::null_mut(); 4];
+    for (i, &base) in GPIO_BANK_BASES.iter().enumerate() {
+      let mapping = mmap(
+        ptr::null_mut(),
+        GPIO_BANK_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_SHARED,
+        mem_file.as_raw_fd(),
+        base as _,
+      );
+      match mapping {
+        Ok(addr) => banks[i] = addr as *mut u32,
+        Err(e) => {
+          // Don't leak the banks that were already mapped
+          for bank in &banks[..i] {
+            let _ = munmap(*bank as *mut _, GPIO_BANK_SIZE);
+          }
+          return Err(Error::from_nix(e, &format!("GPIO bank {}", i), &mem_path));
+        }
+      }
+    }
+
+    // The mappings stay valid after the file is closed
+    Ok(MappedGPIO { banks: banks })
+  }
+
+  /// Drives an output pin high.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::mmap_gpio::{MappedGPIO, MappedPin};
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut gpio = unsafe { MappedGPIO::new(&Board::new()).unwrap() };
+  /// let pin = MappedPin::new(P8_11).unwrap();
+  /// gpio.set_direction(pin, PinDirection::Out);
+  ///
+  /// // Toggle the pin as fast as possible
+  /// loop {
+  ///   gpio.set(pin);
+  ///   gpio.clear(pin);
+  /// }
+  /// ```
+  pub fn set(&mut self, pin: MappedPin) {
+    self.write_reg(pin.bank, GPIO_SETDATAOUT, 1 << pin.bit);
+  }
+
+  /// Drives an output pin low.
+  pub fn clear(&mut self, pin: MappedPin) {
+    self.write_reg(pin.bank, GPIO_CLEARDATAOUT, 1 << pin.bit);
+  }
+
+  /// Sets an output pin either logic high or low.
+  pub fn write(&mut self, pin: MappedPin, state: PinState) {
+    match state {
+      PinState::High => self.set(pin),
+      PinState::Low => self.clear(pin),
+    }
+  }
+
+  /// Reads the logic level of a pin.
+  pub fn read(&self, pin: MappedPin) -> PinState {
+    if self.read_reg(pin.bank, GPIO_DATAIN) & (1 << pin.bit) != 0 {
+      PinState::High
+    } else {
+      PinState::Low
+    }
+  }
+
+  /// Reads the level a pin is being driven to, rather than its actual level.
+  pub fn read_output(&self, pin: MappedPin) -> PinState {
+    if self.read_reg(pin.bank, GPIO_DATAOUT) & (1 << pin.bit) != 0 {
+      PinState::High
+    } else {
+      PinState::Low
+    }
+  }
+
+  /// Sets the direction of a pin as either an input or output.
+  pub fn set_direction(&mut self, pin: MappedPin, direction: PinDirection) {
+    // A set bit in GPIO_OE disables the output driver
+    let oe = self.read_reg(pin.bank, GPIO_OE);
+    self.write_reg(pin.bank, GPIO_OE, match direction {
+      PinDirection::In => oe | (1 << pin.bit),
+      PinDirection::Out => oe & !(1 << pin.bit),
+    });
+  }
+
+  fn read_reg(&self, bank: usize, offset: usize) -> u32 {
+    unsafe { ptr::read_volatile(self.banks[bank].add(offset / 4)) }
+  }
+
+  fn write_reg(&mut self, bank: usize, offset: usize, value: u32) {
+    unsafe { ptr::write_volatile(self.banks[bank].add(offset / 4), value) }
+  }
+}
+
+impl Drop for MappedGPIO {
+  fn drop(&mut self) {
+    for bank in &self.banks {
+      let _ = munmap(*bank as *mut _, GPIO_BANK_SIZE);
+    }
+  }
+}
+
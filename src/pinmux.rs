@@ -0,0 +1,187 @@
+//! The pinmux module.
+//!
+//! Most header pins can be muxed to several functions (see `pins`).
+//! With the cape-universal device tree overlay loaded (the default on recent
+//! BeagleBone images), each pin's function can be changed at runtime through
+//! `/sys/devices/platform/ocp/ocp:P9_14_pinmux/state`, which is what the
+//! `config-pin` utility does.
+//!
+//! The functions here do the same, after checking that the pin actually has
+//! the requested mode.
+//! Writing the state usually requires root.
+//!
+//! Instead of muxing pins by hand, `Board::set_auto_pinmux()` makes the
+//! constructors of `GPIO`, `GPIOGroup`, `I2C`, `SPI`, `UART` and `EQEP`, and
+//! `PWM::from_pin()` and `ECAP::from_pin()`, mux their pins themselves.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libbeaglebone::pinmux::{self, PinMode};
+//! use libbeaglebone::prelude::*;
+//!
+//! let board = Board::new();
+//!
+//! // The equivalent of `config-pin P9.14 pwm`
+//! pinmux::set_mode(&board, P9_14, PinMode::Pwm).unwrap();
+//! assert_eq!(pinmux::mode(&board, P9_14).unwrap(), PinMode::Pwm);
+//!
+//! // P9.14 has no UART signals, so this fails without touching the pin
+//! assert!(pinmux::set_mode(&board, P9_14, PinMode::Uart).is_err());
+//! ```
+
+use board::Board;
+use errors::*;
+use pins::{self, Capabilities, Pin};
+use std::path::PathBuf;
+use std::str::FromStr;
+use util::*;
+
+/// The function a pin is muxed to, as named by cape-universal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinMode {
+  /// The pin's default mode from the device tree.
+  Default,
+  /// GPIO with the pull resistor left as it is.
+  Gpio,
+  /// GPIO with the internal pull-up resistor enabled.
+  GpioPullUp,
+  /// GPIO with the internal pull-down resistor enabled.
+  GpioPullDown,
+  /// GPIO input with the pull resistor disabled.
+  GpioInput,
+  /// PWM output.
+  Pwm,
+  /// UART signal.
+  Uart,
+  /// I2C signal.
+  I2C,
+  /// SPI signal.
+  SPI,
+  /// Quadrature encoder input.
+  Eqep,
+  /// PRU input.
+  PruIn,
+  /// PRU output.
+  PruOut,
+  /// Timer input or output.
+  Timer,
+  /// Any other state, such as `pru_uart`, passed to the kernel as is.
+  Other(String),
+}
+
+impl PinMode {
+  /// Returns the capability a pin needs to be muxed to this mode.
+  fn capability(&self) -> Capabilities {
+    match *self {
+      PinMode::Default | PinMode::Other(_) => Capabilities::empty(),
+      PinMode::Gpio | PinMode::GpioPullUp | PinMode::GpioPullDown | PinMode::GpioInput => {
+        pins::CAP_GPIO
+      }
+      PinMode::Pwm => pins::CAP_PWM,
+      PinMode::Uart => pins::CAP_UART,
+      PinMode::I2C => pins::CAP_I2C,
+      PinMode::SPI => pins::CAP_SPI,
+      PinMode::Eqep => pins::CAP_EQEP,
+      PinMode::PruIn | PinMode::PruOut => pins::CAP_PRU,
+      PinMode::Timer => pins::CAP_TIMER,
+    }
+  }
+
+  /// Returns the cape-universal state selecting this mode on a pin.
+  fn state(&self, pin: Pin) -> String {
+    match *self {
+      PinMode::Default => "default",
+      PinMode::Gpio => "gpio",
+      PinMode::GpioPullUp => "gpio_pu",
+      PinMode::GpioPullDown => "gpio_pd",
+      PinMode::GpioInput => "gpio_input",
+      PinMode::Pwm => {
+        // eCAP2's output has its own state
+        if pin.info().modes.iter().any(|m| m.to_lowercase().starts_with("ecap2")) {
+          "pwm2"
+        } else {
+          "pwm"
+        }
+      }
+      PinMode::Uart => "uart",
+      PinMode::I2C => "i2c",
+      PinMode::SPI => {
+        // The chip select and clock pins have their own states
+        let signal = pin.info().modes.iter().find(|m| m.starts_with("spi")).unwrap_or(&"");
+        if signal.contains("_cs") {
+          "spi_cs"
+        } else if signal.contains("sclk") {
+          "spi_sclk"
+        } else {
+          "spi"
+        }
+      }
+      PinMode::Eqep => "qep",
+      PinMode::PruIn => "pruin",
+      PinMode::PruOut => "pruout",
+      PinMode::Timer => "timer",
+      PinMode::Other(ref state) => state,
+    }
+    .to_string()
+  }
+}
+
+impl FromStr for PinMode {
+  type Err = Error;
+
+  /// Parses a cape-universal state.
+  fn from_str(s: &str) -> Result<PinMode> {
+    Ok(match s {
+         "default" => PinMode::Default,
+         "gpio" => PinMode::Gpio,
+         "gpio_pu" => PinMode::GpioPullUp,
+         "gpio_pd" => PinMode::GpioPullDown,
+         "gpio_input" => PinMode::GpioInput,
+         "pwm" | "pwm2" => PinMode::Pwm,
+         "uart" => PinMode::Uart,
+         "i2c" => PinMode::I2C,
+         "spi" | "spi_cs" | "spi_sclk" => PinMode::SPI,
+         "eqep" | "qep" => PinMode::Eqep,
+         "pruin" => PinMode::PruIn,
+         "pruout" => PinMode::PruOut,
+         "timer" => PinMode::Timer,
+         state => PinMode::Other(state.to_string()),
+       })
+  }
+}
+
+/// Muxes a pin to a mode.
+///
+/// # Errors
+///
+/// Fails with `InvalidPin` if the pin has no such mode, in which case it's
+/// left untouched.
+/// Fails if cape-universal isn't loaded, or if the kernel refuses the state
+/// (e.g. because the pin is reserved by HDMI or the eMMC).
+pub fn set_mode(board: &Board, pin: Pin, mode: PinMode) -> Result<()> {
+  let state = mode.state(pin);
+  if !pin.capabilities().contains(mode.capability()) {
+    bail!(ErrorKind::InvalidPin(format!("{} has no {} mode", pin, state)));
+  }
+  state_path(board, pin).write_file(&pinmux_name(pin), &state)
+}
+
+/// Returns the mode a pin is currently muxed to.
+///
+/// # Errors
+///
+/// Fails if cape-universal isn't loaded or the pin can't be muxed.
+pub fn mode(board: &Board, pin: Pin) -> Result<PinMode> {
+  parse_file(&state_path(board, pin), &pinmux_name(pin))
+}
+
+/// Returns the path of the cape-universal state attribute of a pin.
+fn state_path(board: &Board, pin: Pin) -> PathBuf {
+  let name = pin.header().replace('.', "_");
+  board.sysfs_path(format!("devices/platform/ocp/ocp:{}_pinmux/state", name))
+}
+
+fn pinmux_name(pin: Pin) -> String {
+  format!("{} pinmux", pin)
+}
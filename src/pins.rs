@@ -1,104 +1,540 @@
+//! The pins module.
+//!
+//! Every pin on the BeagleBone Black's P8 and P9 headers is described by a
+//! `PinInfo` entry: the pad it's connected to, its GPIO number, the signal
+//! of each of its eight mux modes, and whether the board already uses it for
+//! HDMI or the eMMC.
+//!
+//! # Examples
+//!
+//! ```
+//! use libbeaglebone::pins::{self, Pin};
+//!
+//! let pin = Pin::from_header("P9.14").unwrap();
+//! assert_eq!(pin.gpio_num(), Some(50));
+//! assert!(pin.capabilities().contains(pins::CAP_PWM));
+//!
+//! // The signal of each mux mode, from mode 0 to mode 7
+//! assert_eq!(pin.info().modes[6], "ehrpwm1A");
+//! ```
+
+use errors::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// A pin on one of the BeagleBone's expansion headers.
+///
+/// `P9_14` is pin 14 of the P9 header, which is written `P9.14` in most
+/// BeagleBone documentation (and by `config-pin`).
 #[allow(bad_style)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Pin {
-  GPIO_P8_3 = 38,
-  GPIO_P8_4 = 39,
-  GPIO_P8_5 = 34,
-  GPIO_P8_6 = 35,
-  GPIO_P8_7 = 66,
-  GPIO_P8_8 = 67,
-  GPIO_P8_9 = 69,
-  GPIO_P8_10 = 68,
-  GPIO_P8_11 = 45,
-  GPIO_P8_12 = 44,
-  GPIO_P8_13 = 23,
-  GPIO_P8_14 = 26,
-  GPIO_P8_15 = 47,
-  GPIO_P8_16 = 46,
-  GPIO_P8_17 = 27,
-  GPIO_P8_18 = 65,
-  GPIO_P8_19 = 22,
-  GPIO_P8_20 = 63,
-  GPIO_P8_21 = 62,
-  GPIO_P8_22 = 37,
-  GPIO_P8_23 = 36,
-  GPIO_P8_24 = 33,
-  GPIO_P8_25 = 32,
-  GPIO_P8_26 = 61,
-  GPIO_P8_27 = 86,
-  GPIO_P8_28 = 88,
-  GPIO_P8_29 = 87,
-  GPIO_P8_30 = 89,
-  GPIO_P8_31 = 10,
-  GPIO_P8_32 = 11,
-  GPIO_P8_33 = 9,
-  GPIO_P8_34 = 81,
-  GPIO_P8_35 = 8,
-  GPIO_P8_36 = 80,
-  GPIO_P8_37 = 78,
-  GPIO_P8_38 = 79,
-  GPIO_P8_39 = 76,
-  GPIO_P8_40 = 77,
-  GPIO_P8_41 = 74,
-  GPIO_P8_42 = 75,
-  GPIO_P8_43 = 72,
-  GPIO_P8_44 = 73,
-  GPIO_P8_45 = 70,
-  GPIO_P8_46 = 71,
-  GPIO_P9_11 = 30,
-  GPIO_P9_12 = 60,
-  GPIO_P9_13 = 31,
-  GPIO_P9_14 = 40,
-  GPIO_P9_15 = 48,
-  GPIO_P9_16 = 51,
-  GPIO_P9_17 = 4,
-  GPIO_P9_18 = 5,
-  GPIO_P9_21 = 3,
-  GPIO_P9_22 = 2,
-  GPIO_P9_23 = 49,
-  GPIO_P9_24 = 15,
-  GPIO_P9_25 = 117,
-  GPIO_P9_26 = 14,
-  GPIO_P9_27 = 125,
-  GPIO_P9_28 = 123,
-  GPIO_P9_29 = 121,
-  GPIO_P9_30 = 122,
-  GPIO_P9_31 = 120,
-  GPIO_P9_41 = 20,
-  GPIO_P9_42 = 7,
-
-  // This is the hackiest of hacks.
-  // To avoid having enum variants point to the same value, we just increase all of the ADC
-  // variants by 1000 (and then subtract them later in the code).
-  // Yeah, I'm not too proud of this one.
-  AIN_0 = 1000,
-  AIN_1 = 1001,
-  AIN_2 = 1002,
-  AIN_3 = 1003,
-  AIN_4 = 1004,
-  AIN_5 = 1005,
-  AIN_6 = 1006,
-  AIN_7 = 1007,
-
-  // Unfortunately it seems like the pin aliases change depending on which cape is loaded,
-  // meaning we'd have to implement a way to adjust the aliases.
-  // That will have to wait for now.
-  // See link below for some more details.
-  // https://groups.google.com/d/msg/beagleboard/1mkf_s_g0vI/55aA84qNAQAJ
-
-  // 0  EHRPWM0A  P9.22,P9.31
-  // 1  EHRPWM0B  P9.21,P9.29
-  // 2  ECAPPWM0  P9.42
-  // 3  EHRPWM1A  P9.14,P8.36
-  // 4  EHRPWM1B  P9.16,P8.34
-  // 5  EHRPWM2A  P8.19,P8.45
-  // 6  EHRPWM2B  P8.13,P8.46
-  // 7  ECAPPWM2  P9.28
-
-  // PWM_P = (0,0),
-  // PWM_P = (0,1),
-  // PWM_P = (2,0),
-  // PWM_P = (2,1),
-  // PWM_P = (4,0),
-  // PWM_P = (4,1),
+  P8_1, P8_2, P8_3, P8_4, P8_5, P8_6, P8_7, P8_8, P8_9, P8_10,
+  P8_11, P8_12, P8_13, P8_14, P8_15, P8_16, P8_17, P8_18, P8_19, P8_20,
+  P8_21, P8_22, P8_23, P8_24, P8_25, P8_26, P8_27, P8_28, P8_29, P8_30,
+  P8_31, P8_32, P8_33, P8_34, P8_35, P8_36, P8_37, P8_38, P8_39, P8_40,
+  P8_41, P8_42, P8_43, P8_44, P8_45, P8_46,
+  P9_1, P9_2, P9_3, P9_4, P9_5, P9_6, P9_7, P9_8, P9_9, P9_10,
+  P9_11, P9_12, P9_13, P9_14, P9_15, P9_16, P9_17, P9_18, P9_19, P9_20,
+  P9_21, P9_22, P9_23, P9_24, P9_25, P9_26, P9_27, P9_28, P9_29, P9_30,
+  P9_31, P9_32, P9_33, P9_34, P9_35, P9_36, P9_37, P9_38, P9_39, P9_40,
+  P9_41, P9_42, P9_43, P9_44, P9_45, P9_46,
+}
+
+/// Which of the BeagleBone Black's on-board peripherals uses a pin.
+///
+/// Reserved pins can only be used after disabling the peripheral, e.g. by
+/// turning off the HDMI or eMMC in `/boot/uEnv.txt`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReservedBy {
+  /// The HDMI framer (LCD and McASP0 audio signals).
+  HDMI,
+  /// The on-board eMMC (MMC1).
+  EMMC,
+}
+
+/// Describes a header pin.
+#[derive(Debug)]
+pub struct PinInfo {
+  /// The pin being described.
+  pub pin: Pin,
+  /// The header location, e.g. `P9.14`.
+  pub header: &'static str,
+  /// The AM335x pad name (or the supply for power pins), e.g. `GPMC_A2`.
+  pub name: &'static str,
+  /// The ZCZ package ball, e.g. `U14`, or an empty string for power pins.
+  pub ball: &'static str,
+  /// The GPIO number, if the pin can be used as a GPIO.
+  pub gpio: Option<u8>,
+  /// The signal of each mux mode from 0 to 7, or an empty string if the mode
+  /// is unused.
+  pub modes: [&'static str; 8],
+  /// The on-board peripheral using the pin, if any.
+  pub reserved: Option<ReservedBy>,
+}
+
+bitflags! {
+  /// The functions a pin can be muxed to.
+  pub struct Capabilities: u16 {
+    /// General-purpose I/O.
+    const CAP_GPIO = 0x001;
+    /// A PWM output (eHRPWM or eCAP).
+    const CAP_PWM = 0x002;
+    /// A UART signal.
+    const CAP_UART = 0x004;
+    /// An I2C signal.
+    const CAP_I2C = 0x008;
+    /// A SPI signal.
+    const CAP_SPI = 0x010;
+    /// A quadrature encoder input (eQEP).
+    const CAP_EQEP = 0x020;
+    /// A PRU input or output.
+    const CAP_PRU = 0x040;
+    /// A timer input or output.
+    const CAP_TIMER = 0x080;
+    /// An analog input.
+    const CAP_ADC = 0x100;
+  }
+}
+
+impl Pin {
+  /// Looks up a pin by its header location.
+  ///
+  /// Accepts `P9.14`, `P9_14` or `P9-14`, in either case.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use libbeaglebone::pins::Pin;
+  ///
+  /// assert_eq!(Pin::from_header("P8.11").unwrap(), Pin::P8_11);
+  /// assert_eq!(Pin::from_header("p9_42").unwrap(), Pin::P9_42);
+  /// assert!(Pin::from_header("P9.47").is_err());
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if `header` doesn't name a pin on the P8 or P9 header.
+  pub fn from_header(header: &str) -> Result<Pin> {
+    let upper = header.trim().to_uppercase();
+    let (offset, rest) = if let Some(rest) = upper.strip_prefix("P8") {
+      (0, rest)
+    } else if let Some(rest) = upper.strip_prefix("P9") {
+      (46, rest)
+    } else {
+      bail!(ErrorKind::InvalidPin(header.to_string()));
+    };
+
+    let num_str = rest.trim_start_matches(['.', '_', '-']);
+    let num = match num_str.parse::<usize>() {
+      Ok(num) if (1..=46).contains(&num) => num,
+      _ => bail!(ErrorKind::InvalidPin(header.to_string())),
+    };
+    Ok(PINS[offset + num - 1].pin)
+  }
+
+  /// Returns the description of the pin.
+  pub fn info(self) -> &'static PinInfo {
+    &PINS[self as usize]
+  }
+
+  /// Returns the header location of the pin, e.g. `P9.14`.
+  pub fn header(self) -> &'static str {
+    self.info().header
+  }
+
+  /// Returns the GPIO number of the pin, if it can be used as a GPIO.
+  pub fn gpio_num(self) -> Option<u8> {
+    self.info().gpio
+  }
+
+  /// Returns the ADC channel of the pin, if it's an analog input.
+  pub fn adc_channel(self) -> Option<u8> {
+    self.info().name.strip_prefix("AIN").and_then(|num| num.parse().ok())
+  }
+
+  /// Returns the on-board peripheral using the pin, if any.
+  pub fn reserved(self) -> Option<ReservedBy> {
+    self.info().reserved
+  }
+
+  /// Returns the functions the pin can be muxed to.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use libbeaglebone::pins::{self, Pin};
+  ///
+  /// let caps = Pin::P9_22.capabilities();
+  /// assert!(caps.contains(pins::CAP_SPI | pins::CAP_UART | pins::CAP_I2C | pins::CAP_PWM));
+  /// assert!(!caps.contains(pins::CAP_ADC));
+  /// ```
+  pub fn capabilities(self) -> Capabilities {
+    let mut caps = self.info()
+                       .modes
+                       .iter()
+                       .fold(Capabilities::empty(), |caps, mode| caps | mode_capabilities(mode));
+    if self.adc_channel().is_some() {
+      caps |= CAP_ADC;
+    }
+    caps
+  }
+}
+
+impl fmt::Display for Pin {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.header())
+  }
 }
+
+impl FromStr for Pin {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Pin> {
+    Pin::from_header(s)
+  }
+}
+
+/// Returns the capabilities provided by a mux mode signal.
+fn mode_capabilities(mode: &str) -> Capabilities {
+  let mode = mode.to_lowercase();
+  if mode.starts_with("gpio") {
+    CAP_GPIO
+  } else if mode.starts_with("pr1_pru") {
+    CAP_PRU
+  } else if mode.starts_with("ehrpwm") && !mode.contains("tripzone") && !mode.contains("sync") ||
+            mode.starts_with("ecap") {
+    CAP_PWM
+  } else if mode.starts_with("uart") {
+    CAP_UART
+  } else if mode.starts_with("i2c") {
+    CAP_I2C
+  } else if mode.starts_with("spi") {
+    CAP_SPI
+  } else if mode.starts_with("eqep") {
+    CAP_EQEP
+  } else if mode.starts_with("timer") {
+    CAP_TIMER
+  } else {
+    Capabilities::empty()
+  }
+}
+
+/// Aliases for pins, for the names used by older versions of this crate and
+/// by the ADC.
+#[allow(bad_style)]
+pub mod aliases {
+  use super::Pin;
+
+  /// Analog input 0.
+  pub const AIN_0: Pin = Pin::P9_39;
+  /// Analog input 1.
+  pub const AIN_1: Pin = Pin::P9_40;
+  /// Analog input 2.
+  pub const AIN_2: Pin = Pin::P9_37;
+  /// Analog input 3.
+  pub const AIN_3: Pin = Pin::P9_38;
+  /// Analog input 4.
+  pub const AIN_4: Pin = Pin::P9_33;
+  /// Analog input 5.
+  pub const AIN_5: Pin = Pin::P9_36;
+  /// Analog input 6.
+  pub const AIN_6: Pin = Pin::P9_35;
+  // Analog input 7 has no header pin; use `ADC::from_channel()` for it.
+
+  macro_rules! gpio_aliases {
+    ($($alias:ident => $pin:ident),*) => (
+      $(
+        #[deprecated(note = "use the Pin::P8_n/Pin::P9_n variants instead")]
+        pub const $alias: Pin = Pin::$pin;
+      )*
+    )
+  }
+
+  gpio_aliases!(
+    GPIO_P8_3 => P8_3, GPIO_P8_4 => P8_4, GPIO_P8_5 => P8_5, GPIO_P8_6 => P8_6,
+    GPIO_P8_7 => P8_7, GPIO_P8_8 => P8_8, GPIO_P8_9 => P8_9, GPIO_P8_10 => P8_10,
+    GPIO_P8_11 => P8_11, GPIO_P8_12 => P8_12, GPIO_P8_13 => P8_13, GPIO_P8_14 => P8_14,
+    GPIO_P8_15 => P8_15, GPIO_P8_16 => P8_16, GPIO_P8_17 => P8_17, GPIO_P8_18 => P8_18,
+    GPIO_P8_19 => P8_19, GPIO_P8_20 => P8_20, GPIO_P8_21 => P8_21, GPIO_P8_22 => P8_22,
+    GPIO_P8_23 => P8_23, GPIO_P8_24 => P8_24, GPIO_P8_25 => P8_25, GPIO_P8_26 => P8_26,
+    GPIO_P8_27 => P8_27, GPIO_P8_28 => P8_28, GPIO_P8_29 => P8_29, GPIO_P8_30 => P8_30,
+    GPIO_P8_31 => P8_31, GPIO_P8_32 => P8_32, GPIO_P8_33 => P8_33, GPIO_P8_34 => P8_34,
+    GPIO_P8_35 => P8_35, GPIO_P8_36 => P8_36, GPIO_P8_37 => P8_37, GPIO_P8_38 => P8_38,
+    GPIO_P8_39 => P8_39, GPIO_P8_40 => P8_40, GPIO_P8_41 => P8_41, GPIO_P8_42 => P8_42,
+    GPIO_P8_43 => P8_43, GPIO_P8_44 => P8_44, GPIO_P8_45 => P8_45, GPIO_P8_46 => P8_46,
+    GPIO_P9_11 => P9_11, GPIO_P9_12 => P9_12, GPIO_P9_13 => P9_13, GPIO_P9_14 => P9_14,
+    GPIO_P9_15 => P9_15, GPIO_P9_16 => P9_16, GPIO_P9_17 => P9_17, GPIO_P9_18 => P9_18,
+    GPIO_P9_21 => P9_21, GPIO_P9_22 => P9_22, GPIO_P9_23 => P9_23, GPIO_P9_24 => P9_24,
+    GPIO_P9_25 => P9_25, GPIO_P9_26 => P9_26, GPIO_P9_27 => P9_27, GPIO_P9_28 => P9_28,
+    GPIO_P9_29 => P9_29, GPIO_P9_30 => P9_30, GPIO_P9_31 => P9_31, GPIO_P9_41 => P9_41,
+    GPIO_P9_42 => P9_42
+  );
+}
+
+macro_rules! power_pin {
+  ($pin:ident, $header:expr, $name:expr) => (
+    PinInfo {
+      pin: Pin::$pin,
+      header: $header,
+      name: $name,
+      ball: "",
+      gpio: None,
+      modes: ["", "", "", "", "", "", "", ""],
+      reserved: None,
+    }
+  )
+}
+
+macro_rules! pin {
+  ($pin:ident, $header:expr, $name:expr, $ball:expr, $gpio:expr, $reserved:expr,
+   [$($mode:expr),*]) => (
+    PinInfo {
+      pin: Pin::$pin,
+      header: $header,
+      name: $name,
+      ball: $ball,
+      gpio: Some($gpio),
+      modes: [$($mode),*],
+      reserved: $reserved,
+    }
+  )
+}
+
+macro_rules! analog_pin {
+  ($pin:ident, $header:expr, $name:expr, $ball:expr) => (
+    PinInfo {
+      pin: Pin::$pin,
+      header: $header,
+      name: $name,
+      ball: $ball,
+      gpio: None,
+      modes: ["", "", "", "", "", "", "", ""],
+      reserved: None,
+    }
+  )
+}
+
+const HDMI: Option<ReservedBy> = Some(ReservedBy::HDMI);
+const EMMC: Option<ReservedBy> = Some(ReservedBy::EMMC);
+
+// The pin table, in the same order as the `Pin` variants.
+// Mux modes are from the AM335x datasheet, reservations from the BeagleBone
+// Black System Reference Manual.
+static PINS: [PinInfo; 92] = [
+  power_pin!(P8_1, "P8.1", "DGND"),
+  power_pin!(P8_2, "P8.2", "DGND"),
+  pin!(P8_3, "P8.3", "GPMC_AD6", "R9", 38, EMMC,
+       ["gpmc_ad6", "mmc1_dat6", "", "", "", "", "", "gpio1_6"]),
+  pin!(P8_4, "P8.4", "GPMC_AD7", "T9", 39, EMMC,
+       ["gpmc_ad7", "mmc1_dat7", "", "", "", "", "", "gpio1_7"]),
+  pin!(P8_5, "P8.5", "GPMC_AD2", "R8", 34, EMMC,
+       ["gpmc_ad2", "mmc1_dat2", "", "", "", "", "", "gpio1_2"]),
+  pin!(P8_6, "P8.6", "GPMC_AD3", "T8", 35, EMMC,
+       ["gpmc_ad3", "mmc1_dat3", "", "", "", "", "", "gpio1_3"]),
+  pin!(P8_7, "P8.7", "GPMC_ADVN_ALE", "R7", 66, None,
+       ["gpmc_advn_ale", "", "timer4", "", "", "", "", "gpio2_2"]),
+  pin!(P8_8, "P8.8", "GPMC_OEN_REN", "T7", 67, None,
+       ["gpmc_oen_ren", "", "timer7", "", "", "", "", "gpio2_3"]),
+  pin!(P8_9, "P8.9", "GPMC_BEN0_CLE", "T6", 69, None,
+       ["gpmc_be0n_cle", "", "timer5", "", "", "", "", "gpio2_5"]),
+  pin!(P8_10, "P8.10", "GPMC_WEN", "U6", 68, None,
+       ["gpmc_wen", "", "timer6", "", "", "", "", "gpio2_4"]),
+  pin!(P8_11, "P8.11", "GPMC_AD13", "R12", 45, None,
+       ["gpmc_ad13", "lcd_data18", "mmc1_dat5", "mmc2_dat1", "eQEP2B_in", "pr1_mii0_txd1",
+        "pr1_pru0_pru_r30_15", "gpio1_13"]),
+  pin!(P8_12, "P8.12", "GPMC_AD12", "T12", 44, None,
+       ["gpmc_ad12", "lcd_data19", "mmc1_dat4", "mmc2_dat0", "eQEP2A_in", "pr1_mii0_txd2",
+        "pr1_pru0_pru_r30_14", "gpio1_12"]),
+  pin!(P8_13, "P8.13", "GPMC_AD9", "T10", 23, None,
+       ["gpmc_ad9", "lcd_data22", "mmc1_dat1", "mmc2_dat5", "ehrpwm2B", "pr1_mii0_col", "",
+        "gpio0_23"]),
+  pin!(P8_14, "P8.14", "GPMC_AD10", "T11", 26, None,
+       ["gpmc_ad10", "lcd_data21", "mmc1_dat2", "mmc2_dat6", "ehrpwm2_tripzone_input",
+        "pr1_mii0_txen", "", "gpio0_26"]),
+  pin!(P8_15, "P8.15", "GPMC_AD15", "U13", 47, None,
+       ["gpmc_ad15", "lcd_data16", "mmc1_dat7", "mmc2_dat3", "eQEP2_strobe",
+        "pr1_ecap0_ecap_capin_apwm_o", "pr1_pru0_pru_r31_15", "gpio1_15"]),
+  pin!(P8_16, "P8.16", "GPMC_AD14", "V13", 46, None,
+       ["gpmc_ad14", "lcd_data17", "mmc1_dat6", "mmc2_dat2", "eQEP2_index", "pr1_mii0_txd0",
+        "pr1_pru0_pru_r31_14", "gpio1_14"]),
+  pin!(P8_17, "P8.17", "GPMC_AD11", "U12", 27, None,
+       ["gpmc_ad11", "lcd_data20", "mmc1_dat3", "mmc2_dat7", "ehrpwm0_synco", "pr1_mii0_txd3",
+        "", "gpio0_27"]),
+  pin!(P8_18, "P8.18", "GPMC_CLK", "V12", 65, None,
+       ["gpmc_clk", "lcd_memory_clk", "gpmc_wait1", "mmc2_clk", "pr1_mii1_crs",
+        "pr1_mdio_mdclk", "mcasp0_fsr", "gpio2_1"]),
+  pin!(P8_19, "P8.19", "GPMC_AD8", "U10", 22, None,
+       ["gpmc_ad8", "lcd_data23", "mmc1_dat0", "mmc2_dat4", "ehrpwm2A", "pr1_mii_mt0_clk", "",
+        "gpio0_22"]),
+  pin!(P8_20, "P8.20", "GPMC_CSN2", "V9", 63, EMMC,
+       ["gpmc_csn2", "gpmc_be1n", "mmc1_cmd", "pr1_edio_data_in7", "pr1_edio_data_out7",
+        "pr1_pru1_pru_r30_13", "pr1_pru1_pru_r31_13", "gpio1_31"]),
+  pin!(P8_21, "P8.21", "GPMC_CSN1", "U9", 62, EMMC,
+       ["gpmc_csn1", "gpmc_clk", "mmc1_clk", "pr1_edio_data_in6", "pr1_edio_data_out6",
+        "pr1_pru1_pru_r30_12", "pr1_pru1_pru_r31_12", "gpio1_30"]),
+  pin!(P8_22, "P8.22", "GPMC_AD5", "V8", 37, EMMC,
+       ["gpmc_ad5", "mmc1_dat5", "", "", "", "", "", "gpio1_5"]),
+  pin!(P8_23, "P8.23", "GPMC_AD4", "U8", 36, EMMC,
+       ["gpmc_ad4", "mmc1_dat4", "", "", "", "", "", "gpio1_4"]),
+  pin!(P8_24, "P8.24", "GPMC_AD1", "V7", 33, EMMC,
+       ["gpmc_ad1", "mmc1_dat1", "", "", "", "", "", "gpio1_1"]),
+  pin!(P8_25, "P8.25", "GPMC_AD0", "U7", 32, EMMC,
+       ["gpmc_ad0", "mmc1_dat0", "", "", "", "", "", "gpio1_0"]),
+  pin!(P8_26, "P8.26", "GPMC_CSN0", "V6", 61, None,
+       ["gpmc_csn0", "", "", "", "", "", "", "gpio1_29"]),
+  pin!(P8_27, "P8.27", "LCD_VSYNC", "U5", 86, HDMI,
+       ["lcd_vsync", "gpmc_a8", "gpmc_a1", "pr1_edio_data_in2", "pr1_edio_data_out2",
+        "pr1_pru1_pru_r30_8", "pr1_pru1_pru_r31_8", "gpio2_22"]),
+  pin!(P8_28, "P8.28", "LCD_PCLK", "V5", 88, HDMI,
+       ["lcd_pclk", "gpmc_a10", "pr1_mii0_crs", "pr1_edio_data_in4", "pr1_edio_data_out4",
+        "pr1_pru1_pru_r30_10", "pr1_pru1_pru_r31_10", "gpio2_24"]),
+  pin!(P8_29, "P8.29", "LCD_HSYNC", "R5", 87, HDMI,
+       ["lcd_hsync", "gpmc_a9", "gpmc_a2", "pr1_edio_data_in3", "pr1_edio_data_out3",
+        "pr1_pru1_pru_r30_9", "pr1_pru1_pru_r31_9", "gpio2_23"]),
+  pin!(P8_30, "P8.30", "LCD_AC_BIAS_EN", "R6", 89, HDMI,
+       ["lcd_ac_bias_en", "gpmc_a11", "pr1_mii1_crs", "pr1_edio_data_in5", "pr1_edio_data_out5",
+        "pr1_pru1_pru_r30_11", "pr1_pru1_pru_r31_11", "gpio2_25"]),
+  pin!(P8_31, "P8.31", "LCD_DATA14", "V4", 10, HDMI,
+       ["lcd_data14", "gpmc_a18", "eQEP1_index", "mcasp0_axr1", "uart5_rxd", "pr1_mii_mr0_clk",
+        "uart5_ctsn", "gpio0_10"]),
+  pin!(P8_32, "P8.32", "LCD_DATA15", "T5", 11, HDMI,
+       ["lcd_data15", "gpmc_a19", "eQEP1_strobe", "mcasp0_ahclkx", "mcasp0_axr3",
+        "pr1_mii0_rxdv", "uart5_rtsn", "gpio0_11"]),
+  pin!(P8_33, "P8.33", "LCD_DATA13", "V3", 9, HDMI,
+       ["lcd_data13", "gpmc_a17", "eQEP1B_in", "mcasp0_fsr", "mcasp0_axr3", "pr1_mii0_rxer",
+        "uart4_rtsn", "gpio0_9"]),
+  pin!(P8_34, "P8.34", "LCD_DATA11", "U4", 81, HDMI,
+       ["lcd_data11", "gpmc_a15", "ehrpwm1B", "mcasp0_ahclkr", "mcasp0_axr2", "pr1_mii0_rxd0",
+        "uart3_rtsn", "gpio2_17"]),
+  pin!(P8_35, "P8.35", "LCD_DATA12", "V2", 8, HDMI,
+       ["lcd_data12", "gpmc_a16", "eQEP1A_in", "mcasp0_aclkr", "mcasp0_axr2",
+        "pr1_mii0_rxlink", "uart4_ctsn", "gpio0_8"]),
+  pin!(P8_36, "P8.36", "LCD_DATA10", "U3", 80, HDMI,
+       ["lcd_data10", "gpmc_a14", "ehrpwm1A", "mcasp0_axr0", "", "pr1_mii0_rxd1", "uart3_ctsn",
+        "gpio2_16"]),
+  pin!(P8_37, "P8.37", "LCD_DATA8", "U1", 78, HDMI,
+       ["lcd_data8", "gpmc_a12", "ehrpwm1_tripzone_input", "mcasp0_aclkx", "uart5_txd",
+        "pr1_mii0_rxd3", "uart2_ctsn", "gpio2_14"]),
+  pin!(P8_38, "P8.38", "LCD_DATA9", "U2", 79, HDMI,
+       ["lcd_data9", "gpmc_a13", "ehrpwm0_synco", "mcasp0_fsx", "uart5_rxd", "pr1_mii0_rxd2",
+        "uart2_rtsn", "gpio2_15"]),
+  pin!(P8_39, "P8.39", "LCD_DATA6", "T3", 76, HDMI,
+       ["lcd_data6", "gpmc_a6", "pr1_edio_data_in6", "eQEP2_index", "pr1_edio_data_out6",
+        "pr1_pru1_pru_r30_6", "pr1_pru1_pru_r31_6", "gpio2_12"]),
+  pin!(P8_40, "P8.40", "LCD_DATA7", "T4", 77, HDMI,
+       ["lcd_data7", "gpmc_a7", "pr1_edio_data_in7", "eQEP2_strobe", "pr1_edio_data_out7",
+        "pr1_pru1_pru_r30_7", "pr1_pru1_pru_r31_7", "gpio2_13"]),
+  pin!(P8_41, "P8.41", "LCD_DATA4", "T1", 74, HDMI,
+       ["lcd_data4", "gpmc_a4", "pr1_mii0_txd1", "eQEP2A_in", "", "pr1_pru1_pru_r30_4",
+        "pr1_pru1_pru_r31_4", "gpio2_10"]),
+  pin!(P8_42, "P8.42", "LCD_DATA5", "T2", 75, HDMI,
+       ["lcd_data5", "gpmc_a5", "pr1_mii0_txd0", "eQEP2B_in", "", "pr1_pru1_pru_r30_5",
+        "pr1_pru1_pru_r31_5", "gpio2_11"]),
+  pin!(P8_43, "P8.43", "LCD_DATA2", "R3", 72, HDMI,
+       ["lcd_data2", "gpmc_a2", "pr1_mii0_txd3", "ehrpwm2_tripzone_input", "",
+        "pr1_pru1_pru_r30_2", "pr1_pru1_pru_r31_2", "gpio2_8"]),
+  pin!(P8_44, "P8.44", "LCD_DATA3", "R4", 73, HDMI,
+       ["lcd_data3", "gpmc_a3", "pr1_mii0_txd2", "ehrpwm0_synco", "", "pr1_pru1_pru_r30_3",
+        "pr1_pru1_pru_r31_3", "gpio2_9"]),
+  pin!(P8_45, "P8.45", "LCD_DATA0", "R1", 70, HDMI,
+       ["lcd_data0", "gpmc_a0", "pr1_mii_mt0_clk", "ehrpwm2A", "", "pr1_pru1_pru_r30_0",
+        "pr1_pru1_pru_r31_0", "gpio2_6"]),
+  pin!(P8_46, "P8.46", "LCD_DATA1", "R2", 71, HDMI,
+       ["lcd_data1", "gpmc_a1", "pr1_mii0_txen", "ehrpwm2B", "", "pr1_pru1_pru_r30_1",
+        "pr1_pru1_pru_r31_1", "gpio2_7"]),
+  power_pin!(P9_1, "P9.1", "DGND"),
+  power_pin!(P9_2, "P9.2", "DGND"),
+  power_pin!(P9_3, "P9.3", "VDD_3V3"),
+  power_pin!(P9_4, "P9.4", "VDD_3V3"),
+  power_pin!(P9_5, "P9.5", "VDD_5V"),
+  power_pin!(P9_6, "P9.6", "VDD_5V"),
+  power_pin!(P9_7, "P9.7", "SYS_5V"),
+  power_pin!(P9_8, "P9.8", "SYS_5V"),
+  power_pin!(P9_9, "P9.9", "PWR_BUT"),
+  power_pin!(P9_10, "P9.10", "SYS_RESETN"),
+  pin!(P9_11, "P9.11", "GPMC_WAIT0", "T17", 30, None,
+       ["gpmc_wait0", "gmii2_crs", "gpmc_csn4", "rmii2_crs_dv", "mmc1_sdcd", "pr1_mii1_col",
+        "uart4_rxd", "gpio0_30"]),
+  pin!(P9_12, "P9.12", "GPMC_BEN1", "U18", 60, None,
+       ["gpmc_be1n", "gmii2_col", "gpmc_csn6", "mmc2_dat3", "gpmc_dir", "pr1_mii1_rxlink",
+        "mcasp0_aclkr", "gpio1_28"]),
+  pin!(P9_13, "P9.13", "GPMC_WPN", "U17", 31, None,
+       ["gpmc_wpn", "gmii2_rxerr", "gpmc_csn5", "rmii2_rxerr", "mmc2_sdcd", "pr1_mii1_txen",
+        "uart4_txd", "gpio0_31"]),
+  pin!(P9_14, "P9.14", "GPMC_A2", "U14", 50, None,
+       ["gpmc_a2", "gmii2_txd3", "rgmii2_td3", "mmc2_dat1", "gpmc_a18", "pr1_mii1_txd2",
+        "ehrpwm1A", "gpio1_18"]),
+  pin!(P9_15, "P9.15", "GPMC_A0", "R13", 48, None,
+       ["gpmc_a0", "gmii2_txen", "rgmii2_tctl", "rmii2_txen", "gpmc_a16", "pr1_mii_mt1_clk",
+        "ehrpwm1_tripzone_input", "gpio1_16"]),
+  pin!(P9_16, "P9.16", "GPMC_A3", "T14", 51, None,
+       ["gpmc_a3", "gmii2_txd2", "rgmii2_td2", "mmc2_dat2", "gpmc_a19", "pr1_mii1_txd1",
+        "ehrpwm1B", "gpio1_19"]),
+  pin!(P9_17, "P9.17", "SPI0_CS0", "A16", 5, None,
+       ["spi0_cs0", "mmc2_sdwp", "i2c1_scl", "ehrpwm0_synci", "pr1_uart0_txd",
+        "pr1_edio_data_in1", "pr1_edio_data_out1", "gpio0_5"]),
+  pin!(P9_18, "P9.18", "SPI0_D1", "B16", 4, None,
+       ["spi0_d1", "mmc1_sdwp", "i2c1_sda", "ehrpwm0_tripzone_input", "pr1_uart0_rxd",
+        "pr1_edio_data_in0", "pr1_edio_data_out0", "gpio0_4"]),
+  pin!(P9_19, "P9.19", "UART1_RTSN", "D17", 13, None,
+       ["uart1_rtsn", "timer5", "dcan0_rx", "i2c2_scl", "spi1_cs1", "pr1_uart0_rts_n",
+        "pr1_edc_latch1_in", "gpio0_13"]),
+  pin!(P9_20, "P9.20", "UART1_CTSN", "D18", 12, None,
+       ["uart1_ctsn", "timer6", "dcan0_tx", "i2c2_sda", "spi1_cs0", "pr1_uart0_cts_n",
+        "pr1_edc_latch0_in", "gpio0_12"]),
+  pin!(P9_21, "P9.21", "SPI0_D0", "B17", 3, None,
+       ["spi0_d0", "uart2_txd", "i2c2_scl", "ehrpwm0B", "pr1_uart0_rts_n", "pr1_edio_latch_in",
+        "EMU3", "gpio0_3"]),
+  pin!(P9_22, "P9.22", "SPI0_SCLK", "A17", 2, None,
+       ["spi0_sclk", "uart2_rxd", "i2c2_sda", "ehrpwm0A", "pr1_uart0_cts_n", "pr1_edio_sof",
+        "EMU2", "gpio0_2"]),
+  pin!(P9_23, "P9.23", "GPMC_A1", "V14", 49, None,
+       ["gpmc_a1", "gmii2_rxdv", "rgmii2_rctl", "mmc2_dat0", "gpmc_a17", "pr1_mii1_txd3",
+        "ehrpwm0_synco", "gpio1_17"]),
+  pin!(P9_24, "P9.24", "UART1_TXD", "D15", 15, None,
+       ["uart1_txd", "mmc2_sdwp", "dcan1_rx", "i2c1_scl", "", "pr1_uart0_txd",
+        "pr1_pru0_pru_r31_16", "gpio0_15"]),
+  pin!(P9_25, "P9.25", "MCASP0_AHCLKX", "A14", 117, HDMI,
+       ["mcasp0_ahclkx", "eQEP0_strobe", "mcasp0_axr3", "mcasp1_axr1", "EMU4",
+        "pr1_pru0_pru_r30_7", "pr1_pru0_pru_r31_7", "gpio3_21"]),
+  pin!(P9_26, "P9.26", "UART1_RXD", "D16", 14, None,
+       ["uart1_rxd", "mmc1_sdwp", "dcan1_tx", "i2c1_sda", "", "pr1_uart0_rxd",
+        "pr1_pru1_pru_r31_16", "gpio0_14"]),
+  pin!(P9_27, "P9.27", "MCASP0_FSR", "C13", 115, None,
+       ["mcasp0_fsr", "eQEP0B_in", "mcasp0_axr3", "mcasp1_fsx", "EMU2", "pr1_pru0_pru_r30_5",
+        "pr1_pru0_pru_r31_5", "gpio3_19"]),
+  pin!(P9_28, "P9.28", "MCASP0_AHCLKR", "C12", 113, HDMI,
+       ["mcasp0_ahclkr", "ehrpwm0_synci", "mcasp0_axr2", "spi1_cs0", "eCAP2_in_PWM2_out",
+        "pr1_pru0_pru_r30_3", "pr1_pru0_pru_r31_3", "gpio3_17"]),
+  pin!(P9_29, "P9.29", "MCASP0_FSX", "B13", 111, HDMI,
+       ["mcasp0_fsx", "ehrpwm0B", "", "spi1_d0", "mmc1_sdcd", "pr1_pru0_pru_r30_1",
+        "pr1_pru0_pru_r31_1", "gpio3_15"]),
+  pin!(P9_30, "P9.30", "MCASP0_AXR0", "D12", 112, None,
+       ["mcasp0_axr0", "ehrpwm0_tripzone_input", "", "spi1_d1", "mmc2_sdcd",
+        "pr1_pru0_pru_r30_2", "pr1_pru0_pru_r31_2", "gpio3_16"]),
+  pin!(P9_31, "P9.31", "MCASP0_ACLKX", "A13", 110, HDMI,
+       ["mcasp0_aclkx", "ehrpwm0A", "", "spi1_sclk", "mmc0_sdcd", "pr1_pru0_pru_r30_0",
+        "pr1_pru0_pru_r31_0", "gpio3_14"]),
+  power_pin!(P9_32, "P9.32", "VDD_ADC"),
+  analog_pin!(P9_33, "P9.33", "AIN4", "C8"),
+  power_pin!(P9_34, "P9.34", "GNDA_ADC"),
+  analog_pin!(P9_35, "P9.35", "AIN6", "A8"),
+  analog_pin!(P9_36, "P9.36", "AIN5", "B8"),
+  analog_pin!(P9_37, "P9.37", "AIN2", "B7"),
+  analog_pin!(P9_38, "P9.38", "AIN3", "A7"),
+  analog_pin!(P9_39, "P9.39", "AIN0", "B6"),
+  analog_pin!(P9_40, "P9.40", "AIN1", "C7"),
+  // P9.41 and P9.42 are each connected to two pads; the second ones (GPIO
+  // #116 and #114) must be left as inputs when using these.
+  pin!(P9_41, "P9.41", "XDMA_EVENT_INTR1", "D14", 20, None,
+       ["xdma_event_intr1", "", "tclkin", "clkout2", "timer7", "pr1_pru0_pru_r31_16", "EMU3",
+        "gpio0_20"]),
+  pin!(P9_42, "P9.42", "ECAP0_IN_PWM0_OUT", "C18", 7, None,
+       ["eCAP0_in_PWM0_out", "uart3_txd", "spi1_cs1", "pr1_ecap0_ecap_capin_apwm_o",
+        "spi1_sclk", "mmc0_sdwp", "xdma_event_intr2", "gpio0_7"]),
+  power_pin!(P9_43, "P9.43", "DGND"),
+  power_pin!(P9_44, "P9.44", "DGND"),
+  power_pin!(P9_45, "P9.45", "DGND"),
+  power_pin!(P9_46, "P9.46", "DGND"),
+];
@@ -14,16 +14,50 @@
 //! for P9.29.
 //! A convenient list of pin identifiers can be found through an online search
 //! of "BeagleBone pinout".
+//!
+//! Which `pwmchipN` drives which output depends on the kernel version, so
+//! `PWM::from_pin()` is usually easier than `PWM::new()`: it finds the chip
+//! by the address of its device tree node.
+//!
+//! The outputs come from two kinds of modules (see `PWMKind`).
+//! The eHRPWMs have two channels, A and B, which share a period: the kernel
+//! refuses a period for one channel while the other is enabled with a
+//! different one.
+//! The eCAPs (on P9.42 and P9.28) have a single channel, but a much longer
+//! maximum period; when not generating a PWM signal, they can measure one
+//! instead (see `ecap`).
 
-use enums::DeviceState;
+use board::Board;
+use enums::{DeviceState, SafeState};
 use errors::*;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use pinmux::{self, PinMode};
+use pins::Pin;
+use std::fs;
+use std::path::{Path, PathBuf};
 use util::*;
 
+/// The maximum period of an eHRPWM in nanoseconds: 65536 ticks of the
+/// 100 MHz clock divided by 1792, the largest prescaler.
+const EHRPWM_MAX_PERIOD_NS: u32 = 1_174_405_120;
+
+/// The device tree node addresses of the eCAP and eHRPWM modules.
+const PWM_ADDRESSES: [&str; 6] = ["48300100", "48300200", "48302100", "48302200", "48304100",
+                                  "48304200"];
+
+/// The kind of module generating a PWM signal.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PWMKind {
+  /// An enhanced high resolution PWM, with two channels (A and B) sharing a
+  /// period of at most about 1.17 s.
+  EHRPWM,
+  /// An enhanced capture module in auxiliary PWM mode, with a single
+  /// channel and a period of up to `u32::MAX` ns.
+  /// The module can't capture input while it generates a PWM signal.
+  ECAP,
+}
+
 /// The state in which the PWM is in, either on or off.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum PWMState {
   /// PWM on
   Enabled,
@@ -31,14 +65,31 @@ pub enum PWMState {
   Disabled,
 }
 
+/// The polarity of the PWM output.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Polarity {
+  /// High for the duty cycle, then low for the rest of the period
+  Normal,
+  /// Low for the duty cycle, then high for the rest of the period
+  Inversed,
+}
+
 /// Represents a PWM device.
+///
+/// When dropped, a PWM this handle exported is put in its safe state (see
+/// `set_safe_state()`).
 #[derive(Debug)]
 pub struct PWM {
+  board: Board,
   pwm_chip_num: u8,
   pwm_num: u8,
   period: u32,
   duty_cycle: u32,
   state: PWMState,
+  polarity: Polarity,
+  kind: Option<PWMKind>,
+  exported: bool,
+  safe_state: SafeState,
 }
 
 impl PWM {
@@ -55,24 +106,90 @@ impl PWM {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new PWM device using PWM chip 0 and PWM 0.
-  /// let mut pwm = PWM::new(0, 0);
+  /// let mut pwm = PWM::new(&Board::new(), 0, 0);
   /// ```
   ///
   /// # Errors
   ///
   /// Fails if either `pwm_chip_num` or `pwm_num` are invalid, i.e. you're
   /// trying to refer to a nonexistent PWM.
-  pub fn new(pwm_chip_num: u8, pwm_num: u8) -> PWM {
+  pub fn new(board: &Board, pwm_chip_num: u8, pwm_num: u8) -> PWM {
+    let chip_path = board.sysfs_path(format!("class/pwm/pwmchip{}", pwm_chip_num));
     PWM {
+      board: board.clone(),
       pwm_chip_num: pwm_chip_num,
       pwm_num: pwm_num,
       period: 0,
       duty_cycle: 0,
       state: PWMState::Disabled,
+      polarity: Polarity::Normal,
+      kind: chip_kind(&chip_path),
+      exported: false,
+      safe_state: SafeState::Unexport,
+    }
+  }
+
+  /// Returns the kind of module generating the PWM signal, if the PWM chip
+  /// is one of the AM335x's.
+  pub fn kind(&self) -> Option<PWMKind> {
+    self.kind
+  }
+
+  /// Creates a new PWM object for the PWM output on a header pin.
+  ///
+  /// The PWM chip and channel are found by walking `/sys/class/pwm` and
+  /// matching each chip's device tree node address (e.g. `48302200.pwm` for
+  /// eHRPWM1), which unlike the chip numbers doesn't change between kernels.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// // P9.14 is EHRPWM1A, i.e. channel 0 of eHRPWM1
+  /// let mut pwm = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// pwm.set_export(DeviceState::Exported).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidPin` if the pin has no PWM output.
+  /// Fails with `NotExported` if no PWM chip matches, e.g. because its
+  /// driver isn't loaded.
+  /// Fails if the board muxes pins and the pin can't be muxed as a PWM.
+  pub fn from_pin(board: &Board, pin: Pin) -> Result<PWM> {
+    let (address, pwm_num) = match pwm_channel(pin) {
+      Some(channel) => channel,
+      None => bail!(ErrorKind::InvalidPin(format!("{} has no PWM output", pin))),
+    };
+    if board.auto_pinmux() {
+      pinmux::set_mode(board, pin, PinMode::Pwm)?;
     }
+
+    let class_path = board.sysfs_path("class/pwm");
+    let entries = fs::read_dir(&class_path)
+      .map_err(|e| Error::from_io(e, "PWM chips", &class_path))?;
+    for entry in entries {
+      let entry = entry.map_err(|e| Error::from_io(e, "PWM chips", &class_path))?;
+      let name = entry.file_name().to_string_lossy().into_owned();
+      if !name.starts_with("pwmchip") {
+        continue;
+      }
+      if let Ok(pwm_chip_num) = name["pwmchip".len()..].parse::<u8>() {
+        if links_to_address(&entry.path(), address) {
+          return Ok(PWM::new(board, pwm_chip_num, pwm_num));
+        }
+      }
+    }
+    // No chip means the PWM's driver or overlay isn't loaded
+    bail!(ErrorKind::NotExported(format!("PWM chip for {} ({})", pin, address), class_path))
   }
 
-  /// Exports the PWM.
+  /// Exports or unexports the PWM.
+  ///
+  /// A PWM exported through this method is reset when the handle is
+  /// dropped, unless the safe state is `SafeState::Leave`; one that was
+  /// already exported, e.g. by another process, is left alone.
   ///
   /// # Examples
   ///
@@ -80,7 +197,7 @@ impl PWM {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new PWM device using PWM chip 0 and PWM 0.
-  /// let mut pwm = PWM::new(0, 0);
+  /// let mut pwm = PWM::new(&Board::new(), 0, 0);
   ///
   /// // Export the PWM.
   /// pwm.set_export(DeviceState::Exported).unwrap();
@@ -90,58 +207,74 @@ impl PWM {
   ///
   /// Fails to export to the PWM if it isn't configured correctly or if the
   /// kernel refuses to execute the instruction.
-  pub fn set_export(&self, state: DeviceState) -> Result<()> {
-    let path = PathBuf::from(format!(
-      "/sys/class/pwm/pwmchip{}/pwm{}",
-      &self.pwm_chip_num,
-      &self.pwm_num
-    ));
+  pub fn set_export(&mut self, state: DeviceState) -> Result<()> {
+    let path = self.pwm_path();
     // If w're trying to export and the pin isn't already exported, try to export
     // it.
     if state == DeviceState::Exported && !path.exists() {
-      File::create(format!(
-        "/sys/class/pwm/pwmchip{}/export",
-        &self.pwm_chip_num
-      ))
-      .chain_err(|| "Failed to open PWM export file")?
-      .write_all(self.pwm_num.to_string().as_bytes())
-      .chain_err(|| {
-        format!(
-          "Failed to export PWM #{}-{}",
-          &self.pwm_chip_num,
-          &self.pwm_num
-        )
-      })?;
+      self
+        .chip_path()
+        .join("export")
+        .write_file(&self.name(), &self.pwm_num.to_string())?;
+      self.exported = true;
     }
     // Try to unexport if the path exists, otherwise the device is unexported and there's nothing
     // to do.
     else if state == DeviceState::Unexported && path.exists() {
-      File::create(format!(
-        "/sys/class/pwm/pwmchip{}/unexport",
-        &self.pwm_chip_num
-      ))
-      .chain_err(|| "Failed to open PWM unexport file")?
-      .write_all(self.pwm_num.to_string().as_bytes())
-      .chain_err(|| {
-        format!(
-          "Failed to unexport PWM #{}-{}",
-          &self.pwm_chip_num,
-          &self.pwm_num
-        )
-      })?;
+      self
+        .chip_path()
+        .join("unexport")
+        .write_file(&self.name(), &self.pwm_num.to_string())?;
+    }
+    if state == DeviceState::Unexported {
+      self.exported = false;
     }
     Ok(())
   }
 
+  /// Returns what happens to the PWM when the handle is dropped.
+  pub fn safe_state(&self) -> SafeState {
+    self.safe_state
+  }
+
+  /// Sets what happens to the PWM when the handle is dropped, including
+  /// when a panic unwinds past it.
+  ///
+  /// The default is `SafeState::Unexport`, which disables and then
+  /// unexports the PWM.
+  /// `SafeState::Disable` only disables it and `SafeState::Leave` leaves it
+  /// running, e.g. for programs that configure a PWM and exit.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut pwm = PWM::new(&Board::new(), 0, 0);
+  /// pwm.set_export(DeviceState::Exported).unwrap();
+  /// pwm.set_period(500_000).unwrap();
+  /// pwm.set_duty_cycle(250_000).unwrap();
+  /// pwm.set_state(PWMState::Enabled).unwrap();
+  ///
+  /// // Keep the PWM running after the program exits
+  /// pwm.set_safe_state(SafeState::Leave);
+  /// ```
+  pub fn set_safe_state(&mut self, safe_state: SafeState) {
+    self.safe_state = safe_state;
+  }
+
   /// Sets the period of the PWM in nanoseconds.
   ///
+  /// The kernel rejects periods shorter than the current duty cycle; use
+  /// `configure()` to change both at once.
+  ///
   /// # Examples
   ///
   /// ```no_run
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new PWM device using PWM chip 0 and PWM 0.
-  /// let mut pwm = PWM::new(0, 0);
+  /// let mut pwm = PWM::new(&Board::new(), 0, 0);
   ///
   /// // Export the PWM.
   /// pwm.set_export(DeviceState::Exported).unwrap();
@@ -152,21 +285,12 @@ impl PWM {
   ///
   /// # Errors
   ///
+  /// Fails with `InvalidValue` if the period is too long for an eHRPWM.
   /// Fails if the pin isn't configured correctly.
   pub fn set_period(&mut self, period_ns: u32) -> Result<()> {
-    let path = format!(
-      "/sys/class/pwm/pwmchip{}/pwm{}/period",
-      &self.pwm_chip_num,
-      &self.pwm_num
-    );
-    path.write_file(&format!("{}", period_ns)).chain_err(|| {
-      format!(
-        "Failed to set PWM #{}-{} period to {}",
-        &self.pwm_chip_num,
-        &self.pwm_num,
-        period_ns
-      )
-    })?;
+    self.check_period(period_ns)?;
+    let path = self.pwm_path().join("period");
+    path.write_file(&self.name(), &period_ns.to_string())?;
     self.period = period_ns;
     Ok(())
   }
@@ -179,7 +303,7 @@ impl PWM {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new PWM device using PWM chip 0 and PWM 0.
-  /// let mut pwm = PWM::new(0, 0);
+  /// let mut pwm = PWM::new(&Board::new(), 0, 0);
   ///
   /// // Export the PWM.
   /// pwm.set_export(DeviceState::Exported).unwrap();
@@ -195,22 +319,10 @@ impl PWM {
   ///
   /// Fails to if the pin isn't configured correctly.
   pub fn set_state(&mut self, state: PWMState) -> Result<()> {
-    let path = format!(
-      "/sys/class/pwm/pwmchip{}/pwm{}/enable",
-      &self.pwm_chip_num,
-      &self.pwm_num
-    );
-    path.write_file(match state {
+    let path = self.pwm_path().join("enable");
+    path.write_file(&self.name(), match state {
       PWMState::Enabled => "1",
       PWMState::Disabled => "0",
-    })
-        .chain_err(|| {
-      format!(
-        "Failed to set PWM #{}-{} state to {:?}",
-        &self.pwm_chip_num,
-        &self.pwm_num,
-        state
-      )
     })?;
     self.state = state;
     Ok(())
@@ -224,7 +336,7 @@ impl PWM {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new PWM device using PWM chip 0 and PWM 0.
-  /// let mut pwm = PWM::new(0, 0);
+  /// let mut pwm = PWM::new(&Board::new(), 0, 0);
   ///
   /// // Export the PWM.
   /// pwm.set_export(DeviceState::Exported).unwrap();
@@ -241,29 +353,129 @@ impl PWM {
   ///
   /// # Errors
   ///
-  /// Fails if the percentage is less than 0 or exceeds 100, i.e. if the duty
-  /// cycle isn't in the period.
+  /// Fails with `InvalidValue` if the percentage is less than 0 or exceeds
+  /// 100, i.e. if the duty cycle isn't in the period.
   /// Fails to if the pin isn't configured correctly.
   pub fn write(&mut self, percentage: f32) -> Result<()> {
-    let path = format!(
-      "/sys/class/pwm/pwmchip{}/pwm{}/duty_cycle",
-      &self.pwm_chip_num,
-      &self.pwm_num
-    );
-    let new_duty_cycle = ((percentage / 100.0) * (self.period as f32)) as u32;
-    path.write_file(&format!("{}", new_duty_cycle)).chain_err(
-      || {
-        format!(
-          "Failed to set PWM #{}-{} duty cycle to {}% (aka {}ns)",
-          &self.pwm_chip_num,
-          &self.pwm_num,
-          percentage,
-          new_duty_cycle
-        )
-      },
-    )?;
-    self.duty_cycle = new_duty_cycle;
-    Ok(())
+    if !(0.0..=100.0).contains(&percentage) {
+      bail!(ErrorKind::InvalidValue(self.name(), percentage.to_string()));
+    }
+    self.set_duty_fraction(percentage / 100.0)
+  }
+
+  /// Sets the duty cycle of the PWM as a fraction of the period, from 0.0 to
+  /// 1.0.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut pwm = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// pwm.set_export(DeviceState::Exported).unwrap();
+  /// pwm.set_frequency_hz(1000.0).unwrap();
+  ///
+  /// // High for a quarter of each period
+  /// pwm.set_duty_fraction(0.25).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the fraction isn't between 0.0 and 1.0, or
+  /// if no period has been set.
+  /// Fails if the pin isn't configured correctly.
+  pub fn set_duty_fraction(&mut self, fraction: f32) -> Result<()> {
+    if !(0.0..=1.0).contains(&fraction) {
+      bail!(ErrorKind::InvalidValue(self.name(), fraction.to_string()));
+    }
+    // The period may have been set by another handle or process
+    let period_ns = self.period()?;
+    if period_ns == 0 {
+      bail!(ErrorKind::InvalidValue(self.name(), "period of 0 ns".to_string()));
+    }
+    self.period = period_ns;
+    let duty_cycle_ns = (fraction as f64 * period_ns as f64).round() as u32;
+    self.set_duty_cycle(duty_cycle_ns)
+  }
+
+  /// Sets the frequency of the PWM in Hz, keeping the duty cycle as the same
+  /// fraction of the period.
+  ///
+  /// The period is rounded to the nearest nanosecond.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut pwm = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// pwm.set_export(DeviceState::Exported).unwrap();
+  ///
+  /// // A 50 Hz signal, as used by hobby servos
+  /// pwm.set_frequency_hz(50.0).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the period would be shorter than 1 ns or
+  /// longer than `u32::MAX` ns (about 4.3 s).
+  /// Fails if the pin isn't configured correctly.
+  pub fn set_frequency_hz(&mut self, frequency_hz: f64) -> Result<()> {
+    let period_ns = (1e9 / frequency_hz).round();
+    if !(period_ns >= 1.0 && period_ns <= u32::MAX as f64) {
+      bail!(ErrorKind::InvalidValue(self.name(), frequency_hz.to_string()));
+    }
+    let period_ns = period_ns as u32;
+    // Scale the duty cycle the kernel has, which may have been set by another
+    // handle or process
+    let (old_period_ns, old_duty_cycle_ns) = (self.period()?, self.duty_cycle()?);
+    let duty_cycle_ns = if old_period_ns == 0 {
+      0
+    } else {
+      (old_duty_cycle_ns as u64 * period_ns as u64 / old_period_ns as u64) as u32
+    };
+    self.configure(period_ns, duty_cycle_ns.min(period_ns))
+  }
+
+  /// Sets both the period and the duty cycle of the PWM in nanoseconds.
+  ///
+  /// The kernel rejects a period shorter than the current duty cycle, and a
+  /// duty cycle longer than the current period, so the two are written in
+  /// whichever order keeps every intermediate state valid.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut pwm = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// pwm.set_export(DeviceState::Exported).unwrap();
+  /// pwm.configure(500_000, 400_000).unwrap();
+  ///
+  /// // Shrinking the period below the old duty cycle works too
+  /// pwm.configure(200_000, 100_000).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the duty cycle exceeds the period, or if
+  /// the period is too long for an eHRPWM.
+  /// Fails if the pin isn't configured correctly.
+  pub fn configure(&mut self, period_ns: u32, duty_cycle_ns: u32) -> Result<()> {
+    self.check_period(period_ns)?;
+    if duty_cycle_ns > period_ns {
+      bail!(ErrorKind::InvalidValue(self.name(), duty_cycle_ns.to_string()));
+    }
+    // The duty cycle is read back, as the PWM may have been configured by
+    // another handle or process.
+    let current_duty_cycle = parse_file::<u32>(&self.pwm_path().join("duty_cycle"), &self.name())?;
+    if period_ns >= current_duty_cycle {
+      self.set_period(period_ns)?;
+      self.set_duty_cycle(duty_cycle_ns)
+    } else {
+      self.set_duty_cycle(duty_cycle_ns)?;
+      self.set_period(period_ns)
+    }
   }
 
   /// Sets the duty cycle of the PWM in nanoseconds.
@@ -274,7 +486,7 @@ impl PWM {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new PWM device using PWM chip 0 and PWM 0.
-  /// let mut pwm = PWM::new(0, 0);
+  /// let mut pwm = PWM::new(&Board::new(), 0, 0);
   ///
   /// // Export the PWM.
   /// pwm.set_export(DeviceState::Exported).unwrap();
@@ -294,22 +506,228 @@ impl PWM {
   /// Fails if the duty cycle exceeds the period.
   /// Fails if the pin isn't configured correctly.
   pub fn set_duty_cycle(&mut self, duty_cycle_ns: u32) -> Result<()> {
-    let path = format!(
-      "/sys/class/pwm/pwmchip{}/pwm{}/duty_cycle",
-      &self.pwm_chip_num,
-      &self.pwm_num
-    );
-    path.write_file(&format!("{}", duty_cycle_ns)).chain_err(
-      || {
-        format!(
-          "Failed to set PWM #{}-{} duty cycle to {}ns",
-          &self.pwm_chip_num,
-          &self.pwm_num,
-          duty_cycle_ns
-        )
-      },
-    )?;
+    let path = self.pwm_path().join("duty_cycle");
+    path.write_file(&self.name(), &duty_cycle_ns.to_string())?;
     self.duty_cycle = duty_cycle_ns;
     Ok(())
   }
+
+  /// Sets the polarity of the PWM.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// let mut pwm = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// pwm.set_export(DeviceState::Exported).unwrap();
+  ///
+  /// // Drive an active-low LED
+  /// pwm.set_polarity(Polarity::Inversed).unwrap();
+  /// pwm.configure(500_000, 100_000).unwrap();
+  /// pwm.set_state(PWMState::Enabled).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM is enabled, as the kernel only changes the polarity of
+  /// disabled PWMs.
+  /// Fails if the pin isn't configured correctly.
+  pub fn set_polarity(&mut self, polarity: Polarity) -> Result<()> {
+    let path = self.pwm_path().join("polarity");
+    path.write_file(&self.name(), match polarity {
+      Polarity::Normal => "normal",
+      Polarity::Inversed => "inversed",
+    })?;
+    self.polarity = polarity;
+    Ok(())
+  }
+
+  /// Reads the period of the PWM in nanoseconds from the kernel.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM isn't exported.
+  pub fn period(&self) -> Result<u32> {
+    parse_file(&self.pwm_path().join("period"), &self.name())
+  }
+
+  /// Reads the duty cycle of the PWM in nanoseconds from the kernel.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM isn't exported.
+  pub fn duty_cycle(&self) -> Result<u32> {
+    parse_file(&self.pwm_path().join("duty_cycle"), &self.name())
+  }
+
+  /// Reads the state (enabled or disabled) of the PWM from the kernel.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM isn't exported.
+  pub fn state(&self) -> Result<PWMState> {
+    match parse_file::<u8>(&self.pwm_path().join("enable"), &self.name())? {
+      0 => Ok(PWMState::Disabled),
+      1 => Ok(PWMState::Enabled),
+      value => bail!(ErrorKind::InvalidValue(self.name(), value.to_string())),
+    }
+  }
+
+  /// Reads the polarity of the PWM from the kernel.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM isn't exported.
+  pub fn polarity(&self) -> Result<Polarity> {
+    let path = self.pwm_path().join("polarity");
+    match path.read_file(&self.name())?.trim() {
+      "normal" => Ok(Polarity::Normal),
+      "inversed" => Ok(Polarity::Inversed),
+      value => bail!(ErrorKind::InvalidValue(self.name(), value.to_string())),
+    }
+  }
+
+  /// Replaces the settings remembered by this handle with the kernel's.
+  ///
+  /// Use this to attach to a PWM that another process already configured,
+  /// so that e.g. `set_duty_fraction()` uses its period.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  ///
+  /// // Take over a PWM that another process left running
+  /// let mut pwm = PWM::new(&Board::new(), 0, 0);
+  /// pwm.refresh().unwrap();
+  /// assert!(!pwm.has_drifted().unwrap());
+  ///
+  /// // The duty cycle is a fraction of the period the PWM was running with
+  /// pwm.set_duty_fraction(0.5).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM isn't exported.
+  pub fn refresh(&mut self) -> Result<()> {
+    self.period = self.period()?;
+    self.duty_cycle = self.duty_cycle()?;
+    self.state = self.state()?;
+    self.polarity = self.polarity()?;
+    Ok(())
+  }
+
+  /// Returns whether the kernel's settings differ from those last set
+  /// through this handle, e.g. because another process changed them.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM isn't exported.
+  pub fn has_drifted(&self) -> Result<bool> {
+    Ok(self.period()? != self.period || self.duty_cycle()? != self.duty_cycle ||
+       self.state()? != self.state || self.polarity()? != self.polarity)
+  }
+
+  /// Puts the PWM in its safe state, if this handle exported it.
+  fn reset(&mut self) -> Result<()> {
+    if !self.exported {
+      return Ok(());
+    }
+    match self.safe_state {
+      SafeState::Leave => Ok(()),
+      SafeState::Disable => self.set_state(PWMState::Disabled),
+      SafeState::Unexport | SafeState::DriveLow => {
+        self.set_state(PWMState::Disabled)?;
+        self.set_export(DeviceState::Unexported)
+      }
+    }
+  }
+
+  /// Checks that the PWM's module can generate a period.
+  fn check_period(&self, period_ns: u32) -> Result<()> {
+    if self.kind == Some(PWMKind::EHRPWM) && period_ns > EHRPWM_MAX_PERIOD_NS {
+      bail!(ErrorKind::InvalidValue(self.name(), period_ns.to_string()));
+    }
+    Ok(())
+  }
+
+  /// Returns the path of the sysfs directory of the exported PWM, e.g. for
+  /// reading attributes this crate doesn't wrap.
+  pub fn path(&self) -> PathBuf {
+    self.pwm_path()
+  }
+
+  fn name(&self) -> String {
+    format!("PWM #{}-{}", self.pwm_chip_num, self.pwm_num)
+  }
+
+  /// Returns the path of the sysfs directory of the PWM chip.
+  fn chip_path(&self) -> PathBuf {
+    self.board
+        .sysfs_path(format!("class/pwm/pwmchip{}", self.pwm_chip_num))
+  }
+
+  /// Returns the path of the sysfs directory of the exported PWM.
+  fn pwm_path(&self) -> PathBuf {
+    self.chip_path().join(format!("pwm{}", self.pwm_num))
+  }
+}
+
+impl Drop for PWM {
+  fn drop(&mut self) {
+    let _ = self.reset();
+  }
+}
+
+/// Returns the device tree node address and channel of the PWM output on a
+/// pin, if it has one.
+fn pwm_channel(pin: Pin) -> Option<(&'static str, u8)> {
+  pin.info().modes.iter().filter_map(|mode| {
+    match mode.to_lowercase().as_str() {
+      "ehrpwm0a" => Some(("48300200", 0)),
+      "ehrpwm0b" => Some(("48300200", 1)),
+      "ehrpwm1a" => Some(("48302200", 0)),
+      "ehrpwm1b" => Some(("48302200", 1)),
+      "ehrpwm2a" => Some(("48304200", 0)),
+      "ehrpwm2b" => Some(("48304200", 1)),
+      "ecap0_in_pwm0_out" => Some(("48300100", 0)),
+      "ecap1_in_pwm1_out" => Some(("48302100", 0)),
+      "ecap2_in_pwm2_out" => Some(("48304100", 0)),
+      _ => None,
+    }
+  }).next()
+}
+
+/// Returns the kind of PWM module at a device tree node address.
+fn address_kind(address: &str) -> PWMKind {
+  if address.ends_with("100") {
+    PWMKind::ECAP
+  } else {
+    PWMKind::EHRPWM
+  }
+}
+
+/// Returns the kind of PWM module a PWM chip belongs to, if it's one of the
+/// AM335x's.
+fn chip_kind(chip_path: &Path) -> Option<PWMKind> {
+  PWM_ADDRESSES.iter()
+               .find(|address| links_to_address(chip_path, address))
+               .map(|address| address_kind(address))
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::pwm::ErrorType for PWM {
+  type Error = Error;
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::pwm::SetDutyCycle for PWM {
+  fn max_duty_cycle(&self) -> u16 {
+    u16::MAX
+  }
+
+  fn set_duty_cycle(&mut self, duty: u16) -> Result<()> {
+    self.set_duty_fraction(duty as f32 / u16::MAX as f32)
+  }
 }
@@ -0,0 +1,264 @@
+//! The ramp module.
+//!
+//! Fading a PWM by writing duty cycles in a sleep loop drifts and jitters as
+//! the loop's own run time and scheduling delays pile up.
+//! `PWM::ramp()` instead moves the PWM to a dedicated thread that sleeps
+//! until absolute deadlines with `clock_nanosleep(TIMER_ABSTIME)`, so late
+//! wake-ups don't accumulate and the ramp ends on time.
+//!
+//! The PWM is handed back once the ramp completes or is cancelled.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libbeaglebone::prelude::*;
+//! use libbeaglebone::ramp::Profile;
+//! use std::time::Duration;
+//!
+//! let mut led = PWM::from_pin(&Board::new(), P9_14).unwrap();
+//! led.set_export(DeviceState::Exported).unwrap();
+//! led.configure(1_000_000, 0).unwrap();
+//! led.set_state(PWMState::Enabled).unwrap();
+//!
+//! // Fade in over two seconds, correcting for the eye's response to light
+//! let ramp = led.ramp(1.0, Duration::from_secs(2), Profile::Gamma(2.2)).unwrap();
+//! let led = ramp.join().unwrap();
+//! ```
+
+use errors::*;
+use libc;
+use pwm::PWM;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The shortest time between two duty cycle updates.
+const MIN_STEP_NS: u64 = 1_000_000;
+
+/// The shape of a ramp between two duty cycles.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Profile {
+  /// Changes the duty cycle at a constant rate.
+  Linear,
+  /// Changes the duty cycle at a constant rate in a perceptual space where
+  /// brightness is `duty ^ (1 / gamma)`, so LED fades look even.
+  /// A gamma of 2.2 suits most LEDs; it must be positive and finite.
+  Gamma(f32),
+  /// Starts and ends slowly, and is fastest halfway through (smoothstep).
+  SCurve,
+}
+
+impl Profile {
+  /// Returns the duty fraction at `t` (from 0.0 to 1.0) of a ramp.
+  fn duty_fraction(&self, from: f32, to: f32, t: f32) -> f32 {
+    match *self {
+      Profile::Linear => from + (to - from) * t,
+      Profile::Gamma(gamma) => {
+        let from = from.powf(1.0 / gamma);
+        let to = to.powf(1.0 / gamma);
+        (from + (to - from) * t).powf(gamma)
+      }
+      Profile::SCurve => from + (to - from) * t * t * (3.0 - 2.0 * t),
+    }
+  }
+}
+
+/// A handle to a ramp running on its own thread.
+///
+/// Dropping the handle lets the ramp run to completion in the background,
+/// after which the PWM is dropped.
+#[derive(Debug)]
+pub struct RampHandle {
+  thread: JoinHandle<Result<PWM>>,
+  cancelled: Arc<AtomicBool>,
+  finished: Arc<AtomicBool>,
+}
+
+impl RampHandle {
+  /// Returns whether the ramp has completed, failed or been cancelled.
+  pub fn is_finished(&self) -> bool {
+    self.finished.load(Ordering::SeqCst)
+  }
+
+  /// Waits for the ramp to complete and returns the PWM.
+  ///
+  /// If the ramp's thread panicked, the panic is passed on to the caller.
+  ///
+  /// # Errors
+  ///
+  /// Fails if a duty cycle couldn't be written, in which case the PWM has
+  /// been dropped and put in its safe state.
+  pub fn join(self) -> Result<PWM> {
+    match self.thread.join() {
+      Ok(res) => res,
+      Err(payload) => panic::resume_unwind(payload),
+    }
+  }
+
+  /// Stops the ramp at its current duty cycle and returns the PWM.
+  ///
+  /// The ramp stops within one step (a PWM period, or 1 ms for fast PWMs).
+  ///
+  /// # Errors
+  ///
+  /// Fails if a duty cycle couldn't be written, in which case the PWM has
+  /// been dropped and put in its safe state.
+  pub fn cancel(self) -> Result<PWM> {
+    self.cancelled.store(true, Ordering::SeqCst);
+    self.join()
+  }
+}
+
+impl PWM {
+  /// Ramps the duty cycle from its current value to `to` (a fraction of the
+  /// period, from 0.0 to 1.0) over `duration`, following `profile`.
+  ///
+  /// The ramp runs on its own thread, which takes the PWM and updates the
+  /// duty cycle once per period (but no more than once per millisecond).
+  /// The returned handle gives the PWM back once the ramp is over.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  /// use libbeaglebone::ramp::Profile;
+  /// use std::thread;
+  /// use std::time::Duration;
+  ///
+  /// let mut motor = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// motor.set_export(DeviceState::Exported).unwrap();
+  /// motor.configure(50_000, 0).unwrap();
+  /// motor.set_state(PWMState::Enabled).unwrap();
+  ///
+  /// // Soft-start the motor, but stop accelerating after a second
+  /// let ramp = motor.ramp(0.8, Duration::from_secs(5), Profile::SCurve).unwrap();
+  /// thread::sleep(Duration::from_secs(1));
+  /// let motor = ramp.cancel().unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if `to` isn't between 0.0 and 1.0, or if a
+  /// gamma profile's gamma isn't positive and finite.
+  /// Fails if the PWM's period and duty cycle can't be read, or if the
+  /// thread can't be started.
+  pub fn ramp(self, to: f32, duration: Duration, profile: Profile) -> Result<RampHandle> {
+    if !(0.0..=1.0).contains(&to) {
+      bail!(ErrorKind::InvalidValue(ramp_name(), to.to_string()));
+    }
+    if let Profile::Gamma(gamma) = profile {
+      if !(gamma > 0.0 && gamma.is_finite()) {
+        bail!(ErrorKind::InvalidValue(ramp_name(), format!("gamma {}", gamma)));
+      }
+    }
+    let period_ns = self.period()?;
+    let from = if period_ns == 0 {
+      0.0
+    } else {
+      self.duty_cycle()? as f32 / period_ns as f32
+    };
+    start(self, period_ns, from, to, duration, profile)
+  }
+}
+
+/// Starts ramping a PWM's duty cycle on a new thread.
+fn start(mut pwm: PWM,
+         period_ns: u32,
+         from: f32,
+         to: f32,
+         duration: Duration,
+         profile: Profile)
+         -> Result<RampHandle> {
+  let step_ns = (period_ns as u64).max(MIN_STEP_NS);
+  let duration_ns = duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64;
+  let steps = (duration_ns / step_ns).max(1);
+
+  let path = pwm.path();
+  let thread_path = path.clone();
+  let cancelled = Arc::new(AtomicBool::new(false));
+  let finished = Arc::new(AtomicBool::new(false));
+  let thread_cancelled = cancelled.clone();
+  let thread_finished = finished.clone();
+
+  let thread = thread::Builder::new()
+    .name("pwm-ramp".to_string())
+    .spawn(move || {
+      let res = (|| {
+        let start = now();
+        for step in 1..steps + 1 {
+          if thread_cancelled.load(Ordering::SeqCst) {
+            break;
+          }
+          // Each deadline is worked out from the start, so rounding errors
+          // don't accumulate and the last step lands on the end
+          let offset_ns = (duration_ns as u128 * step as u128 / steps as u128) as u64;
+          sleep_until(&after(&start, offset_ns), &thread_path)?;
+          let t = step as f32 / steps as f32;
+          let fraction = profile.duty_fraction(from, to, t).max(0.0).min(1.0);
+          pwm.set_duty_cycle((fraction as f64 * period_ns as f64).round() as u32)?;
+        }
+        Ok(())
+      })();
+      thread_finished.store(true, Ordering::SeqCst);
+      res.map(|_| pwm)
+    })
+    .map_err(|e| Error::from_io(e, &ramp_name(), &path))?;
+
+  Ok(RampHandle {
+    thread: thread,
+    cancelled: cancelled,
+    finished: finished,
+  })
+}
+
+/// Returns the current time of the monotonic clock.
+fn now() -> libc::timespec {
+  let mut time = libc::timespec {
+    tv_sec: 0,
+    tv_nsec: 0,
+  };
+  // This can't fail with a valid clock and pointer
+  let _ = unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut time) };
+  time
+}
+
+/// Returns the time `ns` nanoseconds after `time`.
+fn after(time: &libc::timespec, ns: u64) -> libc::timespec {
+  let nsec = time.tv_nsec as u64 + ns;
+  libc::timespec {
+    tv_sec: time.tv_sec + (nsec / 1_000_000_000) as libc::time_t,
+    tv_nsec: (nsec % 1_000_000_000) as libc::c_long,
+  }
+}
+
+/// Sleeps until the monotonic clock reaches a deadline.
+///
+/// Returns immediately if the deadline has already passed, so one late
+/// wake-up doesn't delay the following ones.
+///
+/// `path` is the ramped PWM's sysfs directory, for error reporting.
+fn sleep_until(deadline: &libc::timespec, path: &Path) -> Result<()> {
+  loop {
+    let res = unsafe {
+      libc::clock_nanosleep(libc::CLOCK_MONOTONIC,
+                            libc::TIMER_ABSTIME,
+                            deadline,
+                            ptr::null_mut())
+    };
+    match res {
+      0 => return Ok(()),
+      // Retry if a signal interrupted the sleep
+      libc::EINTR => continue,
+      errno => return Err(Error::from_io(io::Error::from_raw_os_error(errno), &ramp_name(), path)),
+    }
+  }
+}
+
+fn ramp_name() -> String {
+  "PWM ramp".to_string()
+}
@@ -0,0 +1,246 @@
+//! The servo module.
+//!
+//! Hobby (RC) servos are driven by a 50 Hz PWM signal whose pulse width sets
+//! the position: typically 1 ms for one end of travel and 2 ms for the other.
+//! `Servo` wraps a `PWM` and does that maths, using the pulse widths and
+//! travel angle of the particular servo.
+//!
+//! Continuous-rotation servos use the same signal, but the pulse width sets
+//! their speed and direction instead; use `set_speed()` for those.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libbeaglebone::prelude::*;
+//! use libbeaglebone::servo::Servo;
+//!
+//! let mut servo = Servo::new(PWM::new(&Board::new(), 0, 0)).unwrap();
+//!
+//! // The middle of a 180 degree servo is a 1.5 ms pulse
+//! servo.set_angle(90.0).unwrap();
+//! ```
+
+use enums::DeviceState;
+use errors::*;
+use pwm::{PWM, PWMState};
+use std::thread;
+use std::time::Duration;
+
+/// The period of the servo signal (50 Hz) in nanoseconds.
+const SERVO_PERIOD_NS: u32 = 20_000_000;
+
+/// Represents a hobby servo driven by a PWM.
+#[derive(Debug)]
+pub struct Servo {
+  pwm: PWM,
+  min_pulse_ns: u32,
+  max_pulse_ns: u32,
+  travel: f32,
+  slew_rate: Option<f32>,
+  pulse_ns: Option<u32>,
+}
+
+impl Servo {
+  /// Creates a new servo driven by a PWM.
+  ///
+  /// The PWM is exported and given a 20 ms period, but no pulses are sent
+  /// until the servo is first moved.
+  /// The servo defaults to 1-2 ms pulses over 180 degrees of travel.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  /// use libbeaglebone::servo::Servo;
+  ///
+  /// let pwm = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// let mut servo = Servo::new(pwm).unwrap();
+  /// servo.set_angle(45.0).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM can't be exported or configured.
+  pub fn new(mut pwm: PWM) -> Result<Servo> {
+    pwm.set_export(DeviceState::Exported)?;
+    pwm.configure(SERVO_PERIOD_NS, 0)?;
+    Ok(Servo {
+      pwm: pwm,
+      min_pulse_ns: 1_000_000,
+      max_pulse_ns: 2_000_000,
+      travel: 180.0,
+      slew_rate: None,
+      pulse_ns: None,
+    })
+  }
+
+  /// Sets the pulse widths, in nanoseconds, at either end of the servo's
+  /// travel.
+  ///
+  /// Many servos travel further than the nominal 1-2 ms range; check the
+  /// datasheet before widening it, as driving a servo past its end stops can
+  /// damage it.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  /// use libbeaglebone::servo::Servo;
+  ///
+  /// let pwm = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// let mut servo = Servo::new(pwm).unwrap();
+  ///
+  /// // A servo with 0.5-2.5 ms pulses over 270 degrees
+  /// servo.set_pulse_range(500_000, 2_500_000).unwrap();
+  /// servo.set_travel(270.0).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if `min_pulse_ns` isn't shorter than
+  /// `max_pulse_ns`, or if `max_pulse_ns` doesn't fit in the 20 ms period.
+  pub fn set_pulse_range(&mut self, min_pulse_ns: u32, max_pulse_ns: u32) -> Result<()> {
+    if min_pulse_ns >= max_pulse_ns || max_pulse_ns > SERVO_PERIOD_NS {
+      bail!(ErrorKind::InvalidValue(servo_name(),
+                                    format!("{}-{} ns", min_pulse_ns, max_pulse_ns)));
+    }
+    self.min_pulse_ns = min_pulse_ns;
+    self.max_pulse_ns = max_pulse_ns;
+    Ok(())
+  }
+
+  /// Sets the angle, in degrees, the servo travels between the shortest and
+  /// longest pulses.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the travel isn't positive.
+  pub fn set_travel(&mut self, travel: f32) -> Result<()> {
+    if !(travel > 0.0) {
+      bail!(ErrorKind::InvalidValue(servo_name(), travel.to_string()));
+    }
+    self.travel = travel;
+    Ok(())
+  }
+
+  /// Limits how fast the servo moves, in degrees per second.
+  ///
+  /// With a limit set, `set_angle()` and `set_speed()` block while they step
+  /// the pulse width towards its target once per 20 ms period, which avoids
+  /// the current spikes and jerks of sudden moves.
+  /// `None` (the default) moves the servo as fast as it can go.
+  /// The first move after creating or detaching the servo isn't limited, as
+  /// its position is unknown.
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the rate isn't positive.
+  pub fn set_slew_rate(&mut self, slew_rate: Option<f32>) -> Result<()> {
+    if let Some(rate) = slew_rate {
+      if !(rate > 0.0) {
+        bail!(ErrorKind::InvalidValue(servo_name(), rate.to_string()));
+      }
+    }
+    self.slew_rate = slew_rate;
+    Ok(())
+  }
+
+  /// Moves the servo to an angle, from 0 to the travel angle (180 degrees by
+  /// default).
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the angle is outside the servo's travel.
+  /// Fails if the PWM can't be written to.
+  pub fn set_angle(&mut self, angle: f32) -> Result<()> {
+    if !(angle >= 0.0 && angle <= self.travel) {
+      bail!(ErrorKind::InvalidValue(servo_name(), angle.to_string()));
+    }
+    let range = (self.max_pulse_ns - self.min_pulse_ns) as f32;
+    let pulse_ns = self.min_pulse_ns + (range * angle / self.travel).round() as u32;
+    self.move_to(pulse_ns)
+  }
+
+  /// Sets the speed of a continuous-rotation servo, from -1.0 (full speed
+  /// one way) through 0.0 (stopped) to 1.0 (full speed the other way).
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// use libbeaglebone::prelude::*;
+  /// use libbeaglebone::servo::Servo;
+  ///
+  /// let pwm = PWM::from_pin(&Board::new(), P9_14).unwrap();
+  /// let mut wheel = Servo::new(pwm).unwrap();
+  ///
+  /// // Half speed backwards, then stop
+  /// wheel.set_speed(-0.5).unwrap();
+  /// wheel.set_speed(0.0).unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// Fails with `InvalidValue` if the speed is outside -1.0 to 1.0.
+  /// Fails if the PWM can't be written to.
+  pub fn set_speed(&mut self, speed: f32) -> Result<()> {
+    if !(-1.0..=1.0).contains(&speed) {
+      bail!(ErrorKind::InvalidValue(servo_name(), speed.to_string()));
+    }
+    let half_range = (self.max_pulse_ns - self.min_pulse_ns) as f32 / 2.0;
+    let center = self.min_pulse_ns as f32 + half_range;
+    self.move_to((center + half_range * speed).round() as u32)
+  }
+
+  /// Stops sending pulses, which lets most servos go limp.
+  ///
+  /// The next move re-enables the output.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the PWM can't be disabled.
+  pub fn detach(&mut self) -> Result<()> {
+    self.pwm.set_state(PWMState::Disabled)?;
+    self.pulse_ns = None;
+    Ok(())
+  }
+
+  /// Returns the PWM driving the servo.
+  pub fn into_pwm(self) -> PWM {
+    self.pwm
+  }
+
+  /// Changes the pulse width, honouring the slew rate limit.
+  fn move_to(&mut self, target_ns: u32) -> Result<()> {
+    let (current_ns, rate) = match (self.pulse_ns, self.slew_rate) {
+      (Some(current_ns), Some(rate)) => (current_ns, rate),
+      _ => {
+        self.pwm.set_duty_cycle(target_ns)?;
+        self.pulse_ns = Some(target_ns);
+        return self.pwm.set_state(PWMState::Enabled);
+      }
+    };
+
+    // The largest step per period, converted from degrees to nanoseconds
+    let ns_per_degree = (self.max_pulse_ns - self.min_pulse_ns) as f32 / self.travel;
+    let max_step = ((rate * ns_per_degree * SERVO_PERIOD_NS as f32 / 1e9) as u32).max(1);
+
+    let mut pulse_ns = current_ns;
+    while pulse_ns != target_ns {
+      pulse_ns = if target_ns > pulse_ns {
+        pulse_ns + max_step.min(target_ns - pulse_ns)
+      } else {
+        pulse_ns - max_step.min(pulse_ns - target_ns)
+      };
+      self.pwm.set_duty_cycle(pulse_ns)?;
+      self.pulse_ns = Some(pulse_ns);
+      if pulse_ns != target_ns {
+        thread::sleep(Duration::from_millis((SERVO_PERIOD_NS / 1_000_000) as u64));
+      }
+    }
+    Ok(())
+  }
+}
+
+fn servo_name() -> String {
+  "Servo".to_string()
+}
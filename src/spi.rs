@@ -1,7 +1,13 @@
+use board::Board;
 use errors::*;
+use nix;
+use pinmux::{self, PinMode};
+use pins::Pin;
 use std::fs::{File, OpenOptions};
 use std::marker::PhantomData;
+use std::mem::size_of_val;
 use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
 
 // Constants extracted from linux/spi/spidev.h
 bitflags! {
@@ -46,6 +52,14 @@ pub struct spi_ioc_transfer<'a, 'b> {
   tx_buf: u64,
   rx_buf: u64,
   len: u32,
+  speed_hz: u32,
+  delay_usecs: u16,
+  bits_per_word: u8,
+  cs_change: u8,
+  tx_nbits: u8,
+  rx_nbits: u8,
+  word_delay_usecs: u8,
+  pad: u8,
 
   tx_buf_ref: PhantomData<&'a [u8]>,
   rx_buf_ref: PhantomData<&'b mut [u8]>,
@@ -78,6 +92,25 @@ impl<'a, 'b> spi_ioc_transfer<'a, 'b> {
       ..Default::default()
     }
   }
+
+  /// Sends the contents of `buf` and replaces them with the received bytes.
+  pub fn read_write_in_place(buf: &'b mut [u8]) -> Self {
+    spi_ioc_transfer {
+      tx_buf: buf.as_ptr() as *const () as usize as u64,
+      rx_buf: buf.as_mut_ptr() as *const () as usize as u64,
+      len: buf.len() as u32,
+      ..Default::default()
+    }
+  }
+
+  /// A transfer that sends and receives nothing, but keeps the chip selected
+  /// for `delay_usecs` microseconds.
+  pub fn delay(delay_usecs: u16) -> Self {
+    spi_ioc_transfer {
+      delay_usecs: delay_usecs,
+      ..Default::default()
+    }
+  }
 }
 
 pub type SpidevTransfer<'a, 'b> = spi_ioc_transfer<'a, 'b>;
@@ -104,6 +137,7 @@ ioctl!(write set_bits_per_word with SPI_IOC_MAGIC, SPI_IOC_NR_BITS_PER_WORD; u8)
 ioctl!(read  get_max_speed_hz with SPI_IOC_MAGIC, SPI_IOC_NR_MAX_SPEED_HZ; u32);
 ioctl!(write set_max_speed_hz with SPI_IOC_MAGIC, SPI_IOC_NR_MAX_SPEED_HZ; u32);
 ioctl!(write spidev_transfer with SPI_IOC_MAGIC, SPI_IOC_NR_TRANSFER; spi_ioc_transfer);
+ioctl!(write buf spidev_transfer_buf with SPI_IOC_MAGIC, SPI_IOC_NR_TRANSFER; spi_ioc_transfer);
 
 /// Represents a SPI interface.
 #[derive(Debug)]
@@ -112,22 +146,31 @@ pub struct SPI {
   max_speed_hz: u32,
   lsb_first: bool,
   spi_mode: SPIModeFlags,
+  spi_num: u8,
+  spi_path: PathBuf,
   spi_file: File,
 }
 
 impl SPI {
-  pub fn new(spi_num: u8) -> Result<SPI> {
-    let spi_file_path = format!("/dev/spidev{}.0", spi_num);
+  pub fn new(board: &Board, spi_num: u8) -> Result<SPI> {
+    if board.auto_pinmux() {
+      for &pin in spi_pins(spi_num) {
+        pinmux::set_mode(board, pin, PinMode::SPI)?;
+      }
+    }
+    let spi_file_path = board.devfs_path(format!("spidev{}.0", spi_num));
     Ok(SPI {
          bits_per_word: 8,
          max_speed_hz: 10_000,
          lsb_first: false,
          spi_mode: SPI_MODE_0,
+         spi_num: spi_num,
          spi_file: OpenOptions::new()
            .read(true)
            .write(true)
-           .open(spi_file_path)
-           .chain_err(|| format!("Failed to create new SPI device #{}.", spi_num))?,
+           .open(&spi_file_path)
+           .map_err(|e| Error::from_io(e, &format!("SPI #{}", spi_num), &spi_file_path))?,
+         spi_path: spi_file_path,
        })
   }
 
@@ -135,7 +178,7 @@ impl SPI {
     let mut mode: u8 = 0;
     unsafe {
       let _ = get_mode_u8(self.spi_file.as_raw_fd(), &mut mode)
-        .chain_err(|| "Failed to read SPI mode.")?;
+        .map_err(|e| self.error(e))?;
     };
     Ok(mode)
   }
@@ -144,13 +187,13 @@ impl SPI {
     if (mode.bits & 0xFFFFFF00) != 0 {
       unsafe {
         let _ = set_mode_u32(self.spi_file.as_raw_fd(), &mode.bits)
-          .chain_err(|| "Failed to set SPI mode.")?;
+        .map_err(|e| self.error(e))?;
       };
     } else {
       let bits: u8 = mode.bits as u8;
       unsafe {
         let _ = set_mode_u8(self.spi_file.as_raw_fd(), &bits)
-          .chain_err(|| "Failed to set SPI mode.")?;
+        .map_err(|e| self.error(e))?;
       };
     }
     Ok(())
@@ -160,7 +203,7 @@ impl SPI {
     let mut lsb_first: u8 = 0;
     unsafe {
       let _ = get_lsb_first(self.spi_file.as_raw_fd(), &mut lsb_first)
-        .chain_err(|| "Failed to read SPI LSB setting.")?;
+        .map_err(|e| self.error(e))?;
     };
     Ok(lsb_first)
   }
@@ -169,7 +212,7 @@ impl SPI {
     let lsb_first_value: u8 = if lsb_first { 1 } else { 0 };
     unsafe {
       let _ = set_lsb_first(self.spi_file.as_raw_fd(), &lsb_first_value)
-        .chain_err(|| "Failed to set SPI LSB setting.")?;
+        .map_err(|e| self.error(e))?;
     };
     Ok(())
   }
@@ -178,7 +221,7 @@ impl SPI {
     let mut bits_per_word: u8 = 0;
     unsafe {
       let _ = get_bits_per_word(self.spi_file.as_raw_fd(), &mut bits_per_word)
-        .chain_err(|| "Failed to read SPI bits per word.")?;
+        .map_err(|e| self.error(e))?;
     };
     Ok(bits_per_word)
   }
@@ -186,7 +229,7 @@ impl SPI {
   pub fn set_bits_per_word(&self, bits_per_word: u8) -> Result<()> {
     unsafe {
       let _ = set_bits_per_word(self.spi_file.as_raw_fd(), &bits_per_word)
-        .chain_err(|| "Failed to set SPI bits per word.")?;
+        .map_err(|e| self.error(e))?;
     };
     Ok(())
   }
@@ -195,7 +238,7 @@ impl SPI {
     let mut max_speed_hz: u32 = 0;
     unsafe {
       let _ = get_max_speed_hz(self.spi_file.as_raw_fd(), &mut max_speed_hz)
-        .chain_err(|| "Failed to read SPI speed.")?;
+        .map_err(|e| self.error(e))?;
     };
     Ok(max_speed_hz)
   }
@@ -203,7 +246,7 @@ impl SPI {
   pub fn set_max_speed_hz(&self, max_speed_hz: u32) -> Result<()> {
     unsafe {
       let _ = set_max_speed_hz(self.spi_file.as_raw_fd(), &max_speed_hz)
-        .chain_err(|| "Failed to set SPI speed.")?;
+        .map_err(|e| self.error(e))?;
     };
     Ok(())
   }
@@ -212,8 +255,119 @@ impl SPI {
     // rx_buf if present, so there is no need to do any additional work
     unsafe {
       let _ = spidev_transfer(self.spi_file.as_raw_fd(), transfer)
-        .chain_err(|| "failed to transfer over SPI")?;
+        .map_err(|e| self.error(e))?;
+    };
+    Ok(())
+  }
+
+  /// Performs several transfers as a single message, keeping the chip
+  /// selected from the start of the first transfer until the end of the last.
+  pub fn transfer_multiple(&self, transfers: &mut [SpidevTransfer]) -> Result<()> {
+    if transfers.is_empty() {
+      return Ok(());
+    }
+    unsafe {
+      let _ = spidev_transfer_buf(self.spi_file.as_raw_fd(),
+                                  transfers.as_ptr(),
+                                  size_of_val(transfers))
+        .map_err(|e| self.error(e))?;
     };
     Ok(())
   }
+
+  /// Wraps an error returned by an ioctl on the SPI device.
+  fn error(&self, err: nix::Error) -> Error {
+    Error::from_nix(err, &format!("SPI #{}", self.spi_num), &self.spi_path)
+  }
+}
+
+/// Returns the header pins of a SPI bus (CS0, D1, D0 and SCLK), which are
+/// muxed when the board muxes pins.
+fn spi_pins(spi_num: u8) -> &'static [Pin] {
+  match spi_num {
+    0 => &[Pin::P9_17, Pin::P9_18, Pin::P9_21, Pin::P9_22],
+    1 => &[Pin::P9_28, Pin::P9_30, Pin::P9_29, Pin::P9_31],
+    _ => &[],
+  }
+}
+
+/// Appends the transfers for a full-duplex transfer of two buffers that may
+/// differ in length.
+///
+/// Bytes received past the end of `read` are discarded and zeroes are sent
+/// past the end of `write`.
+#[cfg(feature = "hal")]
+fn push_transfer<'a>(transfers: &mut Vec<SpidevTransfer<'a, 'a>>,
+                     read: &'a mut [u8],
+                     write: &'a [u8]) {
+  let len = read.len().min(write.len());
+  let (read, read_rest) = read.split_at_mut(len);
+  let (write, write_rest) = write.split_at(len);
+  if len > 0 {
+    transfers.push(SpidevTransfer::read_write(write, read));
+  }
+  if !read_rest.is_empty() {
+    transfers.push(SpidevTransfer::read(read_rest));
+  }
+  if !write_rest.is_empty() {
+    transfers.push(SpidevTransfer::write(write_rest));
+  }
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::spi::ErrorType for SPI {
+  type Error = Error;
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::spi::SpiBus for SPI {
+  fn read(&mut self, words: &mut [u8]) -> Result<()> {
+    self.transfer_multiple(&mut [SpidevTransfer::read(words)])
+  }
+
+  fn write(&mut self, words: &[u8]) -> Result<()> {
+    self.transfer_multiple(&mut [SpidevTransfer::write(words)])
+  }
+
+  fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<()> {
+    let mut transfers = Vec::new();
+    push_transfer(&mut transfers, read, write);
+    self.transfer_multiple(&mut transfers)
+  }
+
+  fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<()> {
+    self.transfer_multiple(&mut [SpidevTransfer::read_write_in_place(words)])
+  }
+
+  // Transfers are synchronous, so there's never anything to flush
+  fn flush(&mut self) -> Result<()> {
+    Ok(())
+  }
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_hal::spi::SpiDevice for SPI {
+  /// Performs the operations as a single spidev message, so the kernel keeps
+  /// the chip selected for the whole transaction.
+  fn transaction(&mut self, operations: &mut [::embedded_hal::spi::Operation<u8>]) -> Result<()> {
+    use embedded_hal::spi::Operation;
+
+    let mut transfers = Vec::with_capacity(operations.len());
+    for op in operations.iter_mut() {
+      match *op {
+        Operation::Read(ref mut buf) => transfers.push(SpidevTransfer::read(buf)),
+        Operation::Write(buf) => transfers.push(SpidevTransfer::write(buf)),
+        Operation::Transfer(ref mut read, write) => push_transfer(&mut transfers, read, write),
+        Operation::TransferInPlace(ref mut buf) => {
+          transfers.push(SpidevTransfer::read_write_in_place(buf))
+        }
+        Operation::DelayNs(ns) => {
+          // Round up to the microsecond resolution of spidev
+          let delay_us = (ns as u64).div_ceil(1000);
+          transfers.push(SpidevTransfer::delay(delay_us.min(u16::MAX as u64) as u16))
+        }
+      }
+    }
+    self.transfer_multiple(&mut transfers)
+  }
 }
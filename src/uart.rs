@@ -14,14 +14,21 @@
 //! This is currently a simple wrapper around the `serialport` library due to
 //! time constraints.
 
+use board::Board;
 use errors::*;
+use pinmux::{self, PinMode};
+use pins::Pin;
 use serialport::open;
 use serialport::prelude::*;
+use std::io;
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// The direction of the pin, which can be either an input or output.
 #[allow(missing_debug_implementations)]
 pub struct UART {
+  uart_num: u32,
+  port_path: PathBuf,
   port: Box<SerialPort>,
 }
 
@@ -36,18 +43,26 @@ impl UART {
   /// // Create a new UART using BB_UART2.
   /// // Don't forget to enable the UART beforehand using bone_capemgr.
   /// // Consult the module documentation for more information!
-  /// let uart = UART::new(2).unwrap();
+  /// let uart = UART::new(&Board::new(), 2).unwrap();
   /// ```
   ///
   /// # Errors
   ///
   /// Method fails if `uart_num` is an invalid UART port (i.e. isn't within 0-5)
   /// or if the kernel fails to open the port for some other reason.
-  pub fn new(uart_num: u32) -> Result<(UART)> {
-    let port_path = format!("/dev/ttyO{}", uart_num);
+  pub fn new(board: &Board, uart_num: u32) -> Result<(UART)> {
+    if board.auto_pinmux() {
+      for &pin in uart_pins(uart_num) {
+        pinmux::set_mode(board, pin, PinMode::Uart)?;
+      }
+    }
+    let port_path = board.devfs_path(format!("ttyO{}", uart_num));
     Ok(UART {
-         port: open(&port_path)
-           .chain_err(|| format!("Failed to open UART port #{}.", uart_num))?,
+         uart_num: uart_num,
+         port: open(&port_path).map_err(|e| {
+           Error::from_io(e.into(), &format!("UART #{}", uart_num), &port_path)
+         })?,
+         port_path: port_path,
        })
   }
 
@@ -60,7 +75,7 @@ impl UART {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new UART using BB_UART2.
-  /// let mut uart = UART::new(2).unwrap();
+  /// let mut uart = UART::new(&Board::new(), 2).unwrap();
   ///
   /// // Write "hello!" to the UART port.
   /// uart.write("hello!").unwrap();
@@ -72,7 +87,7 @@ impl UART {
   pub fn write(&mut self, data: &str) -> Result<()> {
     self.port
         .write_all(data.as_bytes())
-        .chain_err(|| "Failed to write to UART port.")?;
+        .map_err(|e| self.error(e))?;
     Ok(())
   }
 
@@ -87,7 +102,7 @@ impl UART {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new UART using BB_UART2.
-  /// let mut uart = UART::new(2).unwrap();
+  /// let mut uart = UART::new(&Board::new(), 2).unwrap();
   ///
   /// // Read 10 bytes from the UART port.
   /// uart.read_chars(10).unwrap();
@@ -97,7 +112,7 @@ impl UART {
 
     self.port
         .read_exact(buf.as_mut_slice())
-        .chain_err(|| "Failed to read from to UART port")?;
+        .map_err(|e| self.error(e))?;
 
     Ok(buf)
   }
@@ -114,7 +129,7 @@ impl UART {
   /// use libbeaglebone::prelude::*;
   ///
   /// // Create a new UART using BB_UART2.
-  /// let mut uart = UART::new(2).unwrap();
+  /// let mut uart = UART::new(&Board::new(), 2).unwrap();
   ///
   /// // Read 10 bytes from the UART port.
   /// uart.read_to_string(10).unwrap();
@@ -124,10 +139,15 @@ impl UART {
 
     self.port
         .read_exact(buf.as_mut_slice())
-        .chain_err(|| "Failed to read from to UART port")?;
+        .map_err(|e| self.error(e))?;
 
-    Ok(String::from_utf8(buf)
-         .chain_err(|| "Failed to convert the bytes from the UART port to a string.")?)
+    match String::from_utf8(buf) {
+      Ok(data) => Ok(data),
+      Err(e) => {
+        let value = String::from_utf8_lossy(e.as_bytes()).into_owned();
+        Err(Error::with_chain(e, ErrorKind::InvalidValue(self.name(), value)))
+      }
+    }
   }
 
 
@@ -168,43 +188,90 @@ impl UART {
 
   /// Set the baud rate on the UART port.
   pub fn set_baud_rate(&mut self, baud_rate: BaudRate) -> Result<()> {
-    Ok(self.port
-           .set_baud_rate(baud_rate)
-           .chain_err(|| "Failed to set UART baud rate.")?)
+    self.port
+        .set_baud_rate(baud_rate)
+        .map_err(|e| self.error(e))
   }
 
   /// Set the data bits on the UART port.
   pub fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
-    Ok(self.port
-           .set_data_bits(data_bits)
-           .chain_err(|| "Failed to set UART data bits.")?)
+    self.port
+        .set_data_bits(data_bits)
+        .map_err(|e| self.error(e))
   }
 
   /// Set the flow control on the UART port.
   pub fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
-    Ok(self.port
-           .set_flow_control(flow_control)
-           .chain_err(|| "Failed to set UART flow control.")?)
+    self.port
+        .set_flow_control(flow_control)
+        .map_err(|e| self.error(e))
   }
 
   /// Set the parity on the UART port.
   pub fn set_parity(&mut self, parity: Parity) -> Result<()> {
-    Ok(self.port
-           .set_parity(parity)
-           .chain_err(|| "Failed to set UART parity.")?)
+    self.port
+        .set_parity(parity)
+        .map_err(|e| self.error(e))
   }
 
   /// Set the stop bits on the UART port.
   pub fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
-    Ok(self.port
-           .set_stop_bits(stop_bits)
-           .chain_err(|| "Failed to set UART stop bits.")?)
+    self.port
+        .set_stop_bits(stop_bits)
+        .map_err(|e| self.error(e))
   }
 
   /// Set the timeout on the UART port.
   pub fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
-    Ok(self.port
-           .set_timeout(timeout)
-           .chain_err(|| "Failed to set UART timeout.")?)
+    self.port
+        .set_timeout(timeout)
+        .map_err(|e| self.error(e))
+  }
+
+  fn name(&self) -> String {
+    format!("UART #{}", self.uart_num)
+  }
+
+  /// Wraps an error that occurred while accessing the UART port.
+  fn error<E: Into<io::Error>>(&self, err: E) -> Error {
+    Error::from_io(err.into(), &self.name(), &self.port_path)
+  }
+}
+
+/// Returns the header pins of a UART (TX first), which are muxed when the
+/// board muxes pins.
+///
+/// UART0 is wired to the debug header rather than P8/P9.
+fn uart_pins(uart_num: u32) -> &'static [Pin] {
+  match uart_num {
+    1 => &[Pin::P9_24, Pin::P9_26],
+    2 => &[Pin::P9_21, Pin::P9_22],
+    3 => &[Pin::P9_42],
+    4 => &[Pin::P9_13, Pin::P9_11],
+    5 => &[Pin::P8_37, Pin::P8_38],
+    _ => &[],
+  }
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_io::ErrorType for UART {
+  type Error = Error;
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_io::Read for UART {
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+    io::Read::read(&mut self.port, buf).map_err(|e| self.error(e))
+  }
+}
+
+#[cfg(feature = "hal")]
+impl ::embedded_io::Write for UART {
+  fn write(&mut self, buf: &[u8]) -> Result<usize> {
+    io::Write::write(&mut self.port, buf).map_err(|e| self.error(e))
+  }
+
+  fn flush(&mut self) -> Result<()> {
+    io::Write::flush(&mut self.port).map_err(|e| self.error(e))
   }
 }
@@ -2,57 +2,109 @@
 //! writing to sysfs files.
 
 use errors::*;
-use std::fs::File;
+use nix;
+use nix::poll::{EventFlags, PollFd, poll};
+use std::fs::{self, File};
 use std::io::{Write, Read};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
 pub trait Writeable {
-  fn write_file(self, data: &str) -> Result<()>;
+  fn write_file(self, device: &str, data: &str) -> Result<()>;
 }
 
 pub trait Readable {
-  fn read_file(self) -> Result<String>;
+  fn read_file(self, device: &str) -> Result<String>;
 }
 
-impl<'a> Writeable for &'a str {
+impl<'a, P: AsRef<Path> + ?Sized> Writeable for &'a P {
   /// Writes data to a sysfs device file.
-  fn write_file(self, data: &str) -> Result<()> {
+  ///
+  /// `device` names the device the file belongs to, for error reporting.
+  fn write_file(self, device: &str, data: &str) -> Result<()> {
+    let path = self.as_ref();
     // Open the file (write-only) and write data to it
-    File::create(self)
-      .chain_err(|| format!("Failed to open file {} for writing", self))?
+    File::create(path)
+      .map_err(|e| Error::from_io(e, device, path))?
       .write_all(data.as_bytes())
-      .chain_err(|| format!("Failed to write to file {}", self))?;
+      .map_err(|e| {
+        // The kernel answers EINVAL when it doesn't like the value written
+        if e.raw_os_error() == Some(nix::Errno::EINVAL as i32) {
+          Error::with_chain(e, ErrorKind::InvalidValue(device.to_string(), data.to_string()))
+        } else {
+          Error::from_io(e, device, path)
+        }
+      })?;
     Ok(())
   }
 }
 
-impl Writeable for File {
-  /// Writes data to a sysfs device file.
-  fn write_file(mut self, data: &str) -> Result<()> {
-    let _ = self.write_all(data.as_bytes());
-    Ok(())
-  }
-}
-
-impl<'a> Readable for &'a str {
+impl<'a, P: AsRef<Path> + ?Sized> Readable for &'a P {
   /// Reads from a sysfs device file.
-  fn read_file(self) -> Result<String> {
+  ///
+  /// `device` names the device the file belongs to, for error reporting.
+  fn read_file(self, device: &str) -> Result<String> {
+    let path = self.as_ref();
     let mut value_str = String::new();
 
     // Open the file (read-only) and read it's contents into the string
-    let _ = File::open(self)
-      .chain_err(|| format!("Failed to open file {} for reading", self))?
+    let _ = File::open(path)
+      .map_err(|e| Error::from_io(e, device, path))?
       .read_to_string(&mut value_str)
-      .chain_err(|| format!("Failed to read from file {}", self))?;
+      .map_err(|e| Error::from_io(e, device, path))?;
 
     Ok(value_str)
   }
 }
 
-impl Readable for File {
-  /// Reads from a sysfs device file.
-  fn read_file(mut self) -> Result<String> {
-    let mut value_str = String::new();
-    let _ = self.read_to_string(&mut value_str);
-    Ok(value_str)
+/// Reads a sysfs device file and parses its contents.
+pub fn parse_file<T: FromStr>(path: &Path, device: &str) -> Result<T> {
+  let value_str = path.read_file(device)?;
+  match value_str.trim().parse::<T>() {
+    Ok(value) => Ok(value),
+    Err(_) => bail!(ErrorKind::InvalidValue(device.to_string(), value_str.trim().to_string())),
   }
 }
+
+/// Waits for one of `events` to occur on an open file.
+///
+/// Returns `false` if the timeout elapsed first; `None` waits forever.
+pub fn poll_file(file: &File, events: EventFlags, timeout: Option<Duration>) -> nix::Result<bool> {
+  let timeout_ms = match timeout {
+    // Round up, so a short timeout still waits, and clamp long ones, which
+    // would otherwise wrap around to "forever"
+    Some(t) => {
+      let ms = t.as_secs()
+                .saturating_mul(1000)
+                .saturating_add(t.subsec_nanos().div_ceil(1_000_000) as u64);
+      ms.min(i32::max_value() as u64) as i32
+    }
+    None => -1,
+  };
+
+  let mut fds = [PollFd::new(file.as_raw_fd(), events, EventFlags::empty())];
+  loop {
+    match poll(&mut fds, timeout_ms) {
+      // Retry if a signal interrupted the wait
+      Err(nix::Error::Sys(nix::Errno::EINTR)) => continue,
+      res => return res.map(|ready| ready > 0),
+    }
+  }
+}
+
+/// Returns whether a sysfs device entry belongs to the device tree node at
+/// `address`, e.g. `48302200`.
+///
+/// The entry (or its `device` link, on older kernels) points into the node's
+/// directory, e.g. `.../48302200.pwm/pwm/pwmchip4`.
+pub fn links_to_address(path: &Path, address: &str) -> bool {
+  let prefix = format!("{}.", address);
+  [path.to_path_buf(), path.join("device")]
+    .iter()
+    .filter_map(|path| fs::read_link(path).ok())
+    .any(|target| {
+      target.components().any(|c| c.as_os_str().to_string_lossy().starts_with(&prefix))
+    })
+}
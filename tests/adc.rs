@@ -0,0 +1,27 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::adc::Calibration;
+use libbeaglebone::prelude::*;
+
+#[test]
+fn read_voltage_scales_to_the_reference() {
+  let root = FakeRoot::new("adc");
+  // Half of full scale
+  root.write_sys("bus/iio/devices/iio:device0/in_voltage0_raw", "2048\n");
+
+  let sensor = ADC::new(&root.board(), AIN_0, 1.0).unwrap();
+  assert!((sensor.read_voltage().unwrap() - 0.9).abs() < 0.001);
+}
+
+#[test]
+fn read_voltage_applies_the_calibration() {
+  let root = FakeRoot::new("adc-calibration");
+  root.write_sys("bus/iio/devices/iio:device0/in_voltage0_raw", "2048\n");
+
+  let mut sensor = ADC::new(&root.board(), AIN_0, 1.0).unwrap();
+  sensor.set_calibration(Calibration::new(0.1, 2.0));
+  assert!((sensor.read_voltage().unwrap() - 1.9).abs() < 0.001);
+}
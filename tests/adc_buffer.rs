@@ -0,0 +1,58 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::adc_buffer::{ADCBuffer, ADCScan};
+use libbeaglebone::prelude::*;
+use std::fs;
+
+const IIO_DEVICE: &str = "bus/iio/devices/iio:device0";
+
+/// Builds a fake ADC whose channels all hold 12-bit samples, and whose device
+/// holds `data`.
+fn fake_adc(name: &str, data: &[u8]) -> FakeRoot {
+  let root = FakeRoot::new(name);
+  fs::create_dir_all(root.sys(format!("{}/buffer", IIO_DEVICE))).unwrap();
+  for channel in 0..8 {
+    root.write_sys(format!("{}/scan_elements/in_voltage{}_type", IIO_DEVICE, channel),
+                   "le:u12/16>>0\n");
+  }
+  fs::write(root.dev("iio:device0"), data).unwrap();
+  root
+}
+
+#[test]
+fn read_scan_returns_the_selected_channels() {
+  let root = fake_adc("adc-buffer", &[100, 0, 200, 0, 44, 1, 144, 1]);
+
+  let mut buffer = ADCBuffer::new(&root.board(), &[AIN_0, AIN_2]).unwrap();
+  buffer.set_length(1000).unwrap();
+  buffer.start().unwrap();
+  assert_eq!(buffer.read_scan().unwrap(), vec![100, 200]);
+  assert_eq!(buffer.read_scan().unwrap(), vec![300, 400]);
+
+  let scan_element = |attribute: &str| {
+    root.read_sys(format!("{}/scan_elements/{}", IIO_DEVICE, attribute))
+  };
+  assert_eq!(scan_element("in_voltage0_en"), "1");
+  assert_eq!(scan_element("in_voltage2_en"), "1");
+  assert_eq!(root.read_sys(format!("{}/buffer/length", IIO_DEVICE)), "1000");
+}
+
+#[test]
+fn scan_frames_are_keyed_by_channel_and_timestamped() {
+  let timestamp: i64 = 1_500_000_000_123_456_789;
+  // The samples are padded to the alignment of the 64-bit timestamp
+  let mut data = vec![10, 0, 20, 0, 0, 0, 0, 0];
+  data.extend_from_slice(&timestamp.to_le_bytes());
+  let root = fake_adc("adc-scan", &data);
+  root.write_sys(format!("{}/scan_elements/in_timestamp_type", IIO_DEVICE),
+                 "le:s64/64>>0\n");
+
+  let mut monitor = ADCScan::new(&root.board(), &[AIN_1, AIN_3], true).unwrap();
+  let frame = monitor.read().unwrap();
+  assert_eq!(frame.sample(AIN_1), Some(10));
+  assert_eq!(frame.samples[&3], 20);
+  assert_eq!(frame.timestamp_ns, Some(timestamp));
+}
@@ -0,0 +1,24 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::adc_filter::{Filter, FilteredADC};
+use libbeaglebone::prelude::*;
+
+const AIN0_RAW: &str = "bus/iio/devices/iio:device0/in_voltage0_raw";
+
+#[test]
+fn moving_average_smooths_a_spike() {
+  let root = FakeRoot::new("adc-filter");
+  root.write_sys(AIN0_RAW, "2000\n");
+
+  let adc = ADC::new(&root.board(), AIN_0, 1.0).unwrap();
+  let mut sensor = FilteredADC::new(adc, Filter::MovingAverage(4)).unwrap();
+  sensor.read().unwrap();
+
+  root.write_sys(AIN0_RAW, "4000\n");
+  let reading = sensor.read().unwrap();
+  assert_eq!(reading.value, 3000.0);
+  assert_eq!(reading.variance, 1_000_000.0);
+}
@@ -0,0 +1,15 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::prelude::*;
+
+#[test]
+fn peripherals_use_the_board_roots() {
+  let root = FakeRoot::new("board");
+  root.write_sys("class/gpio/gpio45/value", "1\n");
+
+  let pin = GPIO::new(&root.board(), P8_11).unwrap().into_input().unwrap();
+  assert_eq!(pin.read().unwrap(), PinState::High);
+}
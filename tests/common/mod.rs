@@ -0,0 +1,68 @@
+//! A fake sysfs/devfs tree shared by the integration tests.
+
+#![allow(dead_code)]
+
+use libbeaglebone::prelude::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory holding `sys` and `dev` trees of fake attribute
+/// files.
+///
+/// Each one gets its own directory, so tests can run in parallel, and the
+/// directory is removed when it's dropped, even if the test panics.
+pub struct FakeRoot {
+  root: PathBuf,
+}
+
+impl FakeRoot {
+  /// Creates an empty tree, naming its directory after `name`.
+  pub fn new(name: &str) -> FakeRoot {
+    let root = env::temp_dir().join(format!("libbeaglebone-{}-{}-{}",
+                                            name,
+                                            process::id(),
+                                            NEXT_ID.fetch_add(1, Ordering::SeqCst)));
+    fs::create_dir_all(root.join("sys")).unwrap();
+    fs::create_dir_all(root.join("dev")).unwrap();
+    FakeRoot { root: root }
+  }
+
+  /// Returns a board using the fake trees.
+  pub fn board(&self) -> Board {
+    Board::with_roots(self.root.join("sys"), self.root.join("dev"))
+  }
+
+  /// Returns the path of a file in the fake sysfs tree.
+  pub fn sys<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+    self.root.join("sys").join(path)
+  }
+
+  /// Returns the path of a file in the fake devfs tree.
+  pub fn dev<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+    self.root.join("dev").join(path)
+  }
+
+  /// Writes a file in the fake sysfs tree, creating its directories.
+  pub fn write_sys<P: AsRef<Path>>(&self, path: P, contents: &str) -> PathBuf {
+    let path = self.sys(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, contents).unwrap();
+    path
+  }
+
+  /// Reads a file in the fake sysfs tree.
+  pub fn read_sys<P: AsRef<Path>>(&self, path: P) -> String {
+    fs::read_to_string(self.sys(path)).unwrap()
+  }
+}
+
+impl Drop for FakeRoot {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.root);
+  }
+}
@@ -0,0 +1,43 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::ecap::ECAP;
+use libbeaglebone::errors::ErrorKind;
+use libbeaglebone::prelude::*;
+use std::fs;
+use std::os::unix::fs::symlink;
+
+#[test]
+fn measure_reads_the_latest_period() {
+  let root = FakeRoot::new("ecap");
+  // eCAP0 has captured a 100 kHz signal that is high a quarter of the time
+  let counter_dir = root.sys("devices/platform/ocp/48300000.epwmss/48300100.ecap/counter0");
+  fs::create_dir_all(counter_dir.join("count0")).unwrap();
+  fs::create_dir_all(counter_dir.join("signal1")).unwrap();
+  for (i, ticks) in ["1000", "1250", "2000", "2250"].iter().enumerate() {
+    fs::write(counter_dir.join(format!("count0/capture{}", i)), ticks).unwrap();
+  }
+  fs::write(counter_dir.join("count0/count"), "2300").unwrap();
+  fs::create_dir_all(root.sys("bus/counter/devices")).unwrap();
+  symlink(&counter_dir, root.sys("bus/counter/devices/counter0")).unwrap();
+
+  let ecap = ECAP::from_pin(&root.board(), P9_42).unwrap();
+  let capture = ecap.measure().unwrap();
+  assert_eq!(capture.period_ns, 10_000);
+  assert_eq!(capture.high_ns, 2_500);
+  assert_eq!(capture.frequency_hz(), 100_000.0);
+  assert_eq!(capture.duty_fraction(), 0.25);
+}
+
+#[test]
+fn from_pin_fails_without_a_counter() {
+  let root = FakeRoot::new("ecap-no-counter");
+  fs::create_dir_all(root.sys("bus/counter/devices")).unwrap();
+
+  match *ECAP::from_pin(&root.board(), P9_42).unwrap_err().kind() {
+    ErrorKind::NotExported(..) => {}
+    ref kind => panic!("unexpected error: {:?}", kind),
+  }
+}
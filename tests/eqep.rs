@@ -0,0 +1,32 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::eqep::{EQEP, EQEPMode};
+
+const EQEP2: &str = "devices/platform/ocp/48304000.epwmss/48304180.eqep";
+
+#[test]
+fn read_and_reset_the_position() {
+  let root = FakeRoot::new("eqep");
+  root.write_sys(format!("{}/position", EQEP2), "1234\n");
+
+  let mut encoder = EQEP::new(&root.board(), 2).unwrap();
+  assert_eq!(encoder.read().unwrap(), 1234);
+
+  encoder.reset().unwrap();
+  assert_eq!(encoder.read().unwrap(), 0);
+}
+
+#[test]
+fn relative_mode_with_a_period() {
+  let root = FakeRoot::new("eqep-relative");
+  root.write_sys(format!("{}/position", EQEP2), "0\n");
+
+  let mut encoder = EQEP::new(&root.board(), 2).unwrap();
+  encoder.set_mode(EQEPMode::Relative).unwrap();
+  encoder.set_period(10_000_000).unwrap();
+  assert_eq!(encoder.mode().unwrap(), EQEPMode::Relative);
+  assert_eq!(root.read_sys(format!("{}/period", EQEP2)), "10000000");
+}
@@ -0,0 +1,21 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::prelude::*;
+
+#[test]
+fn drop_leaves_pins_exported_elsewhere_alone() {
+  let root = FakeRoot::new("gpio-safe-state");
+  // Another process has exported GPIO #45
+  root.write_sys("class/gpio/gpio45/value", "0\n");
+
+  let pin = GPIO::new(&root.board(), P8_11).unwrap();
+  let mut pin = pin.into_output(PinState::High).unwrap();
+  pin.set_safe_state(SafeState::DriveLow);
+  drop(pin);
+
+  assert_eq!(root.read_sys("class/gpio/gpio45/direction"), "high");
+  assert!(!root.sys("class/gpio/unexport").exists());
+}
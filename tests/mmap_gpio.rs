@@ -0,0 +1,28 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::mmap_gpio::{MappedGPIO, MappedPin};
+use libbeaglebone::prelude::*;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+#[test]
+fn set_writes_the_bank_register() {
+  let root = FakeRoot::new("mmap-gpio");
+  // A sparse file standing in for /dev/mem
+  File::create(root.dev("mem")).unwrap().set_len(0x481A_F000).unwrap();
+
+  let mut gpio = unsafe { MappedGPIO::new(&root.board()).unwrap() };
+  // GPIO #45 is bit 13 of bank 1, so this writes 1 << 13 to the
+  // GPIO_SETDATAOUT register of GPIO1
+  gpio.set(MappedPin::new(P8_11).unwrap());
+  drop(gpio);
+
+  let mut reg = [0u8; 4];
+  let mut mem = File::open(root.dev("mem")).unwrap();
+  mem.seek(SeekFrom::Start(0x4804_C194)).unwrap();
+  mem.read_exact(&mut reg).unwrap();
+  assert_eq!(u32::from_le_bytes(reg), 1 << 13);
+}
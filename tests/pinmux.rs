@@ -0,0 +1,39 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::pinmux::{self, PinMode};
+use libbeaglebone::prelude::*;
+
+#[test]
+fn set_mode_writes_the_state() {
+  let root = FakeRoot::new("pinmux");
+  root.write_sys("devices/platform/ocp/ocp:P9_14_pinmux/state", "default\n");
+  let board = root.board();
+
+  pinmux::set_mode(&board, P9_14, PinMode::Pwm).unwrap();
+  assert_eq!(pinmux::mode(&board, P9_14).unwrap(), PinMode::Pwm);
+}
+
+#[test]
+fn set_mode_rejects_modes_the_pin_lacks() {
+  let root = FakeRoot::new("pinmux-invalid");
+  root.write_sys("devices/platform/ocp/ocp:P9_14_pinmux/state", "pwm\n");
+  let board = root.board();
+
+  // P9.14 has no UART signals, so this fails without touching the pin
+  assert!(pinmux::set_mode(&board, P9_14, PinMode::Uart).is_err());
+  assert_eq!(pinmux::mode(&board, P9_14).unwrap(), PinMode::Pwm);
+}
+
+#[test]
+fn ecap_outputs_use_the_pwm2_state() {
+  let root = FakeRoot::new("pinmux-ecap");
+  root.write_sys("devices/platform/ocp/ocp:P9_28_pinmux/state", "default\n");
+  let board = root.board();
+
+  pinmux::set_mode(&board, P9_28, PinMode::Pwm).unwrap();
+  assert_eq!(root.read_sys("devices/platform/ocp/ocp:P9_28_pinmux/state"), "pwm2");
+  assert_eq!(pinmux::mode(&board, P9_28).unwrap(), PinMode::Pwm);
+}
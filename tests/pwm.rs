@@ -0,0 +1,55 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::errors::ErrorKind;
+use libbeaglebone::prelude::*;
+use std::fs;
+use std::os::unix::fs::symlink;
+
+#[test]
+fn from_pin_finds_the_chip_by_address() {
+  let root = FakeRoot::new("pwm-from-pin");
+  // eHRPWM1 is pwmchip4
+  let chip_dir = root.sys("devices/platform/ocp/48302000.epwmss/48302200.pwm/pwm/pwmchip4");
+  fs::create_dir_all(&chip_dir).unwrap();
+  fs::create_dir_all(root.sys("class/pwm")).unwrap();
+  symlink(&chip_dir, root.sys("class/pwm/pwmchip4")).unwrap();
+
+  // P9.14 is EHRPWM1A, i.e. channel 0 of eHRPWM1
+  let mut pwm = PWM::from_pin(&root.board(), P9_14).unwrap();
+  pwm.set_export(DeviceState::Exported).unwrap();
+  assert_eq!(fs::read_to_string(chip_dir.join("export")).unwrap(), "0");
+
+  pwm.set_safe_state(SafeState::Leave);
+}
+
+#[test]
+fn from_pin_fails_without_a_chip() {
+  let root = FakeRoot::new("pwm-no-chip");
+  fs::create_dir_all(root.sys("class/pwm")).unwrap();
+
+  match *PWM::from_pin(&root.board(), P9_14).unwrap_err().kind() {
+    ErrorKind::NotExported(..) => {}
+    ref kind => panic!("unexpected error: {:?}", kind),
+  }
+}
+
+#[test]
+fn refresh_adopts_the_kernel_settings() {
+  let root = FakeRoot::new("pwm-refresh");
+  // A PWM that's already running
+  root.write_sys("class/pwm/pwmchip0/pwm0/period", "1000000\n");
+  root.write_sys("class/pwm/pwmchip0/pwm0/duty_cycle", "250000\n");
+  root.write_sys("class/pwm/pwmchip0/pwm0/enable", "1\n");
+  root.write_sys("class/pwm/pwmchip0/pwm0/polarity", "normal\n");
+
+  let mut pwm = PWM::new(&root.board(), 0, 0);
+  assert!(pwm.has_drifted().unwrap());
+
+  pwm.refresh().unwrap();
+  assert!(!pwm.has_drifted().unwrap());
+  pwm.set_duty_fraction(0.5).unwrap();
+  assert_eq!(pwm.duty_cycle().unwrap(), 500_000);
+}
@@ -0,0 +1,20 @@
+extern crate libbeaglebone;
+
+mod common;
+
+use common::FakeRoot;
+use libbeaglebone::prelude::*;
+use libbeaglebone::servo::Servo;
+
+#[test]
+fn set_angle_writes_the_pulse_width() {
+  let root = FakeRoot::new("servo");
+  root.write_sys("class/pwm/pwmchip0/pwm0/duty_cycle", "0\n");
+
+  let mut servo = Servo::new(PWM::new(&root.board(), 0, 0)).unwrap();
+
+  // The middle of a 180 degree servo is a 1.5 ms pulse
+  servo.set_angle(90.0).unwrap();
+  assert_eq!(root.read_sys("class/pwm/pwmchip0/pwm0/duty_cycle"), "1500000");
+  assert_eq!(root.read_sys("class/pwm/pwmchip0/pwm0/period"), "20000000");
+}